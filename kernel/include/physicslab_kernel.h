@@ -13,18 +13,162 @@ extern "C" {
 #define PL_STATUS_INVALID_HANDLE 2
 #define PL_STATUS_POLICY_DENIED 3
 #define PL_STATUS_INTERNAL_ERROR 4
+#define PL_STATUS_CURSOR_END 5
+#define PL_STATUS_IO_ERROR 6
+#define PL_STATUS_TIME_LIMIT_REACHED 7
+#define PL_STATUS_NUMERICAL_OVERFLOW 8
+
+// Error details (refine the status code, see pl_last_error_detail)
+#define PL_DETAIL_NONE 0
+#define PL_DETAIL_STEP_LIMIT 1
+#define PL_DETAIL_WORLD_LIMIT 2
+
+// Predicates for pl_world_filter
+#define PL_FILTER_LANDED 0
+#define PL_FILTER_ASLEEP 1
+#define PL_FILTER_DIVERGED 2
+#define PL_FILTER_RISING 3
+
+// Units for pl_world_set_gravity_units (converted to m/s^2)
+#define PL_GRAVITY_UNIT_MPS2 0
+#define PL_GRAVITY_UNIT_FTPS2 1
+#define PL_GRAVITY_UNIT_STANDARD_G 2
+
+// Event kinds reported by pl_world_step_to_event
+#define PL_EVENT_TIMEOUT 0
+#define PL_EVENT_GROUND 1
+#define PL_EVENT_APEX 2
+
+// Force terms reported by pl_world_dominant_force
+#define PL_FORCE_GRAVITY 0
+#define PL_FORCE_REGIONS 1
+#define PL_FORCE_BUOYANCY 2
+
+typedef struct PlWorldConfig {
+    double y0;
+    double vy0;
+    double g;
+    double noise_amplitude;
+} PlWorldConfig;
+
+typedef struct PlState {
+    double t;
+    double y;
+    double vy;
+} PlState;
 
 // Lifecycle
 uint64_t pl_world_create(double y0, double vy0);
 void pl_world_destroy(uint64_t handle);
+int32_t pl_world_destroy_batch(const uint64_t* handles, uint32_t count, int32_t* out_status);
+uint64_t pl_world_count(void);
+uint64_t pl_world_cursor_begin(void);
+int32_t pl_world_cursor_next(uint64_t cursor, uint64_t* out_handle);
+void pl_world_cursor_end(uint64_t cursor);
+int32_t pl_handle_stats(uint64_t* out_min, uint64_t* out_max, uint64_t* out_next);
+uint32_t pl_world_list(uint64_t* out_handles, uint32_t cap);
+uint32_t pl_world_filter(int32_t predicate, uint64_t* out_handles, uint32_t cap);
+uint64_t pl_total_steps_executed(void);
+
+// Configuration
+int32_t pl_set_max_worlds(uint32_t limit);
+uint32_t pl_get_max_worlds(void);
+int32_t pl_set_default_gravity(double g);
+double pl_get_default_gravity(void);
+int32_t pl_get_defaults(PlWorldConfig* out);
+int32_t pl_world_set_gravity(uint64_t handle, double g);
+int32_t pl_world_set_gravity_units(uint64_t handle, int32_t unit, double value);
+int32_t pl_world_get_gravity(uint64_t handle, double* out_g);
+int32_t pl_world_set_gravity_vector(uint64_t handle, double gx, double gy, double gz);
+int32_t pl_world_schedule_gravity(uint64_t handle, double at_t, double new_g);
+int32_t pl_world_add_region(uint64_t handle, double y_low, double y_high, double extra_accel);
+int32_t pl_world_clear_regions(uint64_t handle);
+int32_t pl_world_set_buoyancy(uint64_t handle, double fluid_density, double object_volume, double fluid_top_y);
+int32_t pl_world_set_gravity_table(uint64_t handle, const double* altitudes, const double* gravities, uint32_t count);
+int32_t pl_world_set_bounds(uint64_t handle, double floor_y, double ceiling_y, double restitution);
+int32_t pl_world_get_ground(uint64_t handle, double* out_ground_y, double* out_restitution, int32_t* out_enabled);
+// mode: 0 reflect, 1 clamp, 2 wrap
+int32_t pl_world_set_boundary_mode(uint64_t handle, int32_t mode);
+int32_t pl_world_set_contact_epsilon(uint64_t handle, double eps);
+int32_t pl_world_set_time_limit(uint64_t handle, double t_max);
+int32_t pl_world_set_time_scale(uint64_t handle, double scale);
+int32_t pl_world_set_time_offset(uint64_t handle, double offset);
+int32_t pl_world_set_restitution_jitter(uint64_t handle, double jitter, uint64_t seed);
+int32_t pl_world_set_flush_denormals(uint64_t handle, int32_t enable);
+int32_t pl_world_set_rollback(uint64_t handle, int32_t enable);
+int32_t pl_world_set_noise(uint64_t handle, uint64_t seed, double amplitude);
+int32_t pl_seed_all(uint64_t master_seed);
 
 // Simulation
 int32_t pl_world_step(uint64_t handle, double dt, uint32_t steps);
 int32_t pl_world_get_state(uint64_t handle, double* out_t, double* out_y, double* out_vy);
+int32_t pl_world_get_true_state(uint64_t handle, double* out_t, double* out_y, double* out_vy);
+int32_t pl_world_set_measurement_noise(uint64_t handle, double y_sigma, double vy_sigma, uint64_t seed);
+int32_t pl_world_equals(uint64_t handle_a, uint64_t handle_b, double tol, int32_t* out_equal);
+int32_t pl_world_step_count(uint64_t handle, uint64_t* out_count);
+int32_t pl_world_net_acceleration(uint64_t handle, double* out_a);
+int32_t pl_world_dominant_force(uint64_t handle, int32_t* out_kind, double* out_magnitude);
+// No drag model exists yet: always writes 0 for a live world.
+int32_t pl_world_drag_force(uint64_t handle, double* out_f);
+int32_t pl_world_integrator_order(uint64_t handle, uint32_t* out_order);
+int32_t pl_world_is_linear(uint64_t handle, int32_t* out);
+int32_t pl_world_origin(uint64_t handle, double* out_launch_y, double* out_launch_vy, double* out_launch_t);
+int32_t pl_world_check_stability(uint64_t handle, double dt, int32_t* out_stable);
+int32_t pl_world_max_speed(uint64_t handle, double* out_v);
+int32_t pl_world_airborne_time(uint64_t handle, double* out_t);
+int32_t pl_world_energy_dissipated(uint64_t handle, double* out_joules);
+int32_t pl_world_has_diverged(uint64_t handle, int32_t* out);
+int32_t pl_world_age_seconds(uint64_t handle, double* out_age);
+int32_t pl_world_last_step_nanos(uint64_t handle, uint64_t* out_ns);
+int32_t pl_world_get_state_struct(uint64_t handle, PlState* out);
+uint32_t pl_world_get_state_proto(uint64_t handle, uint8_t* out_buf, uint32_t buf_len);
+uint64_t pl_world_add_body(uint64_t handle, double y, double vy, double mass, double radius);
+int32_t pl_world_get_body_state(uint64_t handle, uint64_t body, double* out_y, double* out_vy);
+int32_t pl_world_step_get(uint64_t handle, double dt, uint32_t steps, double* out_t, double* out_y, double* out_vy);
+int32_t pl_world_ensemble(const PlWorldConfig* cfg, uint64_t base_seed, uint32_t count, double dt, uint32_t steps, double* out_y, double* out_vy);
+// Skips dt/steps validation: bad dt silently yields NaN state.
+int32_t pl_world_step_unchecked(uint64_t handle, double dt, uint32_t steps);
+// Audit aid: writes vy after the kick and y after the drift, before contacts.
+// Debug builds only; release libraries do not export it.
+int32_t pl_world_step_trace(uint64_t handle, double dt, double* out_y, double* out_vy);
+// accel_cb runs under the registry lock and must not call back into the kernel.
+int32_t pl_world_step_custom(uint64_t handle, double dt, uint32_t steps, double (*accel_cb)(double t, double y, double vy, void* user), void* user);
+// cond_cb runs under the registry lock and must not call back into the kernel.
+int32_t pl_world_step_until(uint64_t handle, double dt, uint32_t max_steps, int32_t (*cond_cb)(double t, double y, double vy, void* user), void* user, uint32_t* out_steps);
+int32_t pl_world_step_with_error(uint64_t handle, double dt, uint32_t steps, double* out_error);
+int32_t pl_world_probe(uint64_t handle, double dt, uint32_t steps, double* out_t, double* out_y, double* out_vy);
+int32_t pl_world_trajectory_to_file(uint64_t handle, double dt, uint32_t steps, const uint8_t* path, uint32_t path_len);
+// cb runs without kernel locks held; the arrays are valid only during the call.
+int32_t pl_world_stream(uint64_t handle, double dt, uint32_t steps, uint32_t batch, void (*cb)(const double* ts, const double* ys, const double* vys, uint32_t n, void* user), void* user);
+uint64_t pl_link_spring(uint64_t handle_a, uint64_t handle_b, double k, double rest_length);
+int32_t pl_unlink(uint64_t link_id);
+int32_t pl_step_linked(double dt, uint32_t steps);
+int32_t pl_total_momentum(const uint64_t* handles, uint32_t count, double* out_p);
+int32_t pl_total_energy(const uint64_t* handles, uint32_t count, double* out_total);
+int32_t pl_world_trajectory_stats(uint64_t handle, double dt, uint32_t steps, double* out_min_y, double* out_max_y, double* out_mean_y, double* out_final_vy);
+int32_t pl_world_step_to_event(uint64_t handle, double dt_max, double* out_event_t, int32_t* out_kind);
+int32_t pl_world_step_to_rest(uint64_t handle, double dt, double v_eps, double a_eps, uint32_t* out_steps);
+
+// Persistence (returns bytes needed; writes only if buf_len is large enough)
+uint32_t pl_snapshot_current_version(void);
+uint32_t pl_snapshot_all(uint8_t* out_buf, uint32_t buf_len);
+int32_t pl_restore_all(const uint8_t* buf, uint32_t buf_len);
 
 // Error inspection
 int32_t pl_last_error_code(void);
+int32_t pl_last_error_detail(void);
 uint32_t pl_last_error_message(uint8_t* out_buf, uint32_t buf_len);
+uint32_t pl_last_error_message_len(void);
+int32_t pl_world_last_error_code(uint64_t handle);
+uint32_t pl_world_last_error_message(uint64_t handle, uint8_t* out_buf, uint32_t buf_len);
+
+// Diagnostics: called for every non-OK error; must not re-enter the kernel.
+// msg is UTF-8 of length len, not NUL-terminated. Pass NULL to disable.
+typedef void (*pl_log_callback)(int32_t code, const uint8_t* msg, uint32_t len);
+void pl_set_log_callback(pl_log_callback cb);
+
+// Testing only: not safe while other threads use the kernel
+void pl_reset_for_testing(void);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,566 @@
+// Registry archive layout (all integers and floats little-endian):
+//
+//   magic   4 bytes  "PLAR"
+//   version u32      1..=ARCHIVE_VERSION
+//   count   u32      number of worlds
+//   count x world record
+//   link_count u32, then link_count x link record (version 22 and later)
+//
+// Version 1 record:
+//   handle u64
+//   t, y, vy, g f64
+//
+// Version 2 record (version 1 fields, then):
+//   noise_amplitude f64, noise_rng u64
+//
+// Version 3 record (version 2 fields, then):
+//   table_len u32, then table_len x (altitude, g) f64 pairs
+//
+// Version 4 record (version 3 fields, then):
+//   has_bounds u8, then floor_y, ceiling_y, restitution f64 if 1
+//
+// Version 5 record (version 4 fields, then):
+//   measurement y_sigma f64, vy_sigma f64, rng u64
+//
+// Version 6 record (version 5 fields, then):
+//   step_count u64
+//
+// Version 7 record (version 6 fields, then):
+//   time_scale f64
+//
+// Version 8 record (version 7 fields, then):
+//   restitution_jitter f64, jitter_rng u64
+//
+// Version 9 record (version 8 fields, then):
+//   body_count u32, then body_count x (y, vy, mass, radius) f64
+//
+// Version 10 record (version 9 fields, then):
+//   flush_denormals u8
+//
+// Version 11 record (version 10 fields, then):
+//   contact_epsilon f64
+//
+// Version 12 record (version 11 fields, then):
+//   max_speed f64
+//
+// Version 13 record (version 12 fields, then):
+//   time_limit f64 (+infinity when unset)
+//
+// Version 14 record (version 13 fields, then):
+//   schedule_len u32, then schedule_len x (at_t, g) f64 pairs
+//
+// Version 15 record (version 14 fields, then):
+//   boundary_mode u8 (0 reflect, 1 clamp, 2 wrap)
+//
+// Version 16 record (version 15 fields, then):
+//   last_contact_t f64
+//
+// Version 17 record (version 16 fields, then):
+//   region_count u32, then region_count x (y_low, y_high, extra_accel) f64
+//
+// Version 18 record (version 17 fields, then):
+//   time_offset f64
+//
+// Version 19 record (version 18 fields, then):
+//   created_unix_nanos u64 (wall-clock creation time)
+//
+// Version 20 record (version 19 fields, then):
+//   rollback u8
+//   diverged u8
+//
+// Version 21 record (version 20 fields, then):
+//   has_buoyancy u8, then fluid_density, object_volume, fluid_top_y f64 if 1
+//
+// Version 22 adds no world fields; the world records are followed by the
+// spring links between archived worlds, each:
+//   id u64, handle_a u64, handle_b u64
+//   k, rest_length f64
+//
+// Version 23 record (version 21 fields, then):
+//   energy_dissipated f64
+//
+// Older versions restore with defaults for the fields they lack, as if those
+// worlds had just been created, and with no links. Decoding is strict: wrong
+// magic, a version newer than this kernel, short or trailing bytes, handle 0, duplicate
+// handles, non-finite floats or out-of-range parameters reject the archive.
+
+use super::bodies::Body;
+use super::{BoundaryMode, Bounds, Buoyancy, Measurement, SpringLink, World};
+
+const MAGIC: &[u8; 4] = b"PLAR";
+pub(crate) const ARCHIVE_VERSION: u32 = 23;
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_f64s(buf: &mut Vec<u8>, values: &[f64]) {
+    for value in values {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub(crate) fn encode<'a>(
+    worlds: impl ExactSizeIterator<Item = (u64, &'a World)>,
+    links: &[(u64, SpringLink)],
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    put_u32(&mut buf, ARCHIVE_VERSION);
+    put_u32(&mut buf, worlds.len() as u32);
+    for (handle, world) in worlds {
+        put_u64(&mut buf, handle);
+        put_f64s(&mut buf, &[world.t, world.y, world.vy, world.g]);
+        put_f64s(&mut buf, &[world.noise_amplitude]);
+        put_u64(&mut buf, world.rng_state);
+        put_u32(&mut buf, world.gravity_table.len() as u32);
+        for &(altitude, g) in &world.gravity_table {
+            put_f64s(&mut buf, &[altitude, g]);
+        }
+        match &world.bounds {
+            Some(b) => {
+                buf.push(1);
+                put_f64s(&mut buf, &[b.floor_y, b.ceiling_y, b.restitution]);
+            }
+            None => buf.push(0),
+        }
+        let m = &world.measurement;
+        put_f64s(&mut buf, &[m.y_sigma, m.vy_sigma]);
+        put_u64(&mut buf, m.rng_state);
+        put_u64(&mut buf, world.step_count);
+        put_f64s(&mut buf, &[world.time_scale]);
+        put_f64s(&mut buf, &[world.restitution_jitter]);
+        put_u64(&mut buf, world.jitter_rng_state);
+        put_u32(&mut buf, world.bodies.len() as u32);
+        for body in &world.bodies {
+            put_f64s(&mut buf, &[body.y, body.vy, body.mass, body.radius]);
+        }
+        buf.push(u8::from(world.flush_denormals));
+        put_f64s(&mut buf, &[world.contact_epsilon]);
+        put_f64s(&mut buf, &[world.max_speed]);
+        put_f64s(&mut buf, &[world.time_limit]);
+        put_u32(&mut buf, world.gravity_schedule.len() as u32);
+        for &(at_t, g) in &world.gravity_schedule {
+            put_f64s(&mut buf, &[at_t, g]);
+        }
+        buf.push(world.boundary_mode.code());
+        put_f64s(&mut buf, &[world.last_contact_t]);
+        put_u32(&mut buf, world.regions.len() as u32);
+        for &(low, high, accel) in &world.regions {
+            put_f64s(&mut buf, &[low, high, accel]);
+        }
+        put_f64s(&mut buf, &[world.time_offset]);
+        put_u64(&mut buf, world.created_unix_nanos);
+        buf.push(u8::from(world.rollback));
+        buf.push(u8::from(world.diverged));
+        match &world.buoyancy {
+            Some(b) => {
+                buf.push(1);
+                put_f64s(&mut buf, &[b.fluid_density, b.object_volume, b.fluid_top_y]);
+            }
+            None => buf.push(0),
+        }
+        put_f64s(&mut buf, &[world.energy_dissipated]);
+    }
+    put_u32(&mut buf, links.len() as u32);
+    for (id, link) in links {
+        put_u64(&mut buf, *id);
+        put_u64(&mut buf, link.a);
+        put_u64(&mut buf, link.b);
+        put_f64s(&mut buf, &[link.k, link.rest_length]);
+    }
+    buf
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        if self.bytes.len() < N {
+            return Err("archive truncated");
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().expect("split_at returned N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, &'static str> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, &'static str> {
+        let value = self.take().map(f64::from_le_bytes)?;
+        if !value.is_finite() {
+            return Err("archive contains non-finite value");
+        }
+        Ok(value)
+    }
+
+    // Rejects lengths that could not possibly fit in the remaining bytes
+    // before anything is allocated for them.
+    fn len(&mut self, item_size: usize) -> Result<usize, &'static str> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(item_size) > self.bytes.len() {
+            return Err("archive truncated");
+        }
+        Ok(len)
+    }
+}
+
+fn check(ok: bool, message: &'static str) -> Result<(), &'static str> {
+    if ok {
+        Ok(())
+    } else {
+        Err(message)
+    }
+}
+
+fn decode_v2_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.noise_amplitude = reader.f64()?;
+    check(world.noise_amplitude >= 0.0, "archive contains negative noise amplitude")?;
+    world.rng_state = reader.u64()?;
+    Ok(())
+}
+
+fn decode_v3_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    let table_len = reader.len(16)?;
+    for _ in 0..table_len {
+        world.gravity_table.push((reader.f64()?, reader.f64()?));
+    }
+    check(
+        world.gravity_table.windows(2).all(|pair| pair[0].0 < pair[1].0),
+        "archive contains unsorted gravity table",
+    )
+}
+
+fn decode_v4_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.bounds = match reader.u8()? {
+        0 => None,
+        1 => {
+            let bounds = Bounds {
+                floor_y: reader.f64()?,
+                ceiling_y: reader.f64()?,
+                restitution: reader.f64()?,
+            };
+            check(bounds.floor_y < bounds.ceiling_y, "archive contains inverted bounds")?;
+            check(
+                (0.0..=1.0).contains(&bounds.restitution),
+                "archive contains out-of-range restitution",
+            )?;
+            Some(bounds)
+        }
+        _ => return Err("archive contains invalid bounds flag"),
+    };
+    Ok(())
+}
+
+fn decode_v5_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.measurement = Measurement {
+        y_sigma: reader.f64()?,
+        vy_sigma: reader.f64()?,
+        rng_state: reader.u64()?,
+    };
+    check(
+        world.measurement.y_sigma >= 0.0 && world.measurement.vy_sigma >= 0.0,
+        "archive contains negative measurement sigma",
+    )
+}
+
+fn decode_v6_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.step_count = reader.u64()?;
+    Ok(())
+}
+
+fn decode_v7_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.time_scale = reader.f64()?;
+    check(world.time_scale >= 0.0, "archive contains negative time scale")
+}
+
+fn decode_v8_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.restitution_jitter = reader.f64()?;
+    check(
+        (0.0..=1.0).contains(&world.restitution_jitter),
+        "archive contains out-of-range restitution jitter",
+    )?;
+    world.jitter_rng_state = reader.u64()?;
+    Ok(())
+}
+
+fn decode_v9_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    let body_count = reader.len(32)?;
+    for _ in 0..body_count {
+        let body = Body {
+            y: reader.f64()?,
+            vy: reader.f64()?,
+            mass: reader.f64()?,
+            radius: reader.f64()?,
+        };
+        check(body.mass > 0.0 && body.radius >= 0.0, "archive contains invalid body")?;
+        world.bodies.push(body);
+    }
+    Ok(())
+}
+
+fn decode_v10_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.flush_denormals = match reader.u8()? {
+        0 => false,
+        1 => true,
+        _ => return Err("archive contains invalid flush flag"),
+    };
+    Ok(())
+}
+
+fn decode_v11_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.contact_epsilon = reader.f64()?;
+    if !(world.contact_epsilon.is_finite() && world.contact_epsilon >= 0.0) {
+        return Err("archive contains invalid contact epsilon");
+    }
+    Ok(())
+}
+
+fn decode_v12_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.max_speed = reader.f64()?;
+    if !(world.max_speed.is_finite() && world.max_speed >= 0.0) {
+        return Err("archive contains invalid max speed");
+    }
+    Ok(())
+}
+
+fn decode_v13_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    // Read raw: +infinity is the valid "no limit" value.
+    world.time_limit = reader.take().map(f64::from_le_bytes)?;
+    if world.time_limit.is_nan() || world.time_limit < world.t {
+        return Err("archive contains invalid time limit");
+    }
+    Ok(())
+}
+
+fn decode_v14_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    let schedule_len = reader.len(16)?;
+    for _ in 0..schedule_len {
+        world.gravity_schedule.push((reader.f64()?, reader.f64()?));
+    }
+    check(
+        world.gravity_schedule.windows(2).all(|pair| pair[0].0 <= pair[1].0)
+            && world.gravity_schedule.first().is_none_or(|&(at_t, _)| at_t > world.t),
+        "archive contains invalid gravity schedule",
+    )
+}
+
+fn decode_v15_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.boundary_mode =
+        BoundaryMode::from_code(i32::from(reader.u8()?)).ok_or("archive contains invalid boundary mode")?;
+    Ok(())
+}
+
+fn decode_v16_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.last_contact_t = reader.f64()?;
+    check(world.last_contact_t <= world.t, "archive contains invalid contact time")
+}
+
+fn decode_v17_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    let region_count = reader.len(24)?;
+    for _ in 0..region_count {
+        let region = (reader.f64()?, reader.f64()?, reader.f64()?);
+        check(region.0 < region.1, "archive contains inverted region")?;
+        world.regions.push(region);
+    }
+    Ok(())
+}
+
+fn decode_v18_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.time_offset = reader.f64()?;
+    Ok(())
+}
+
+fn decode_v19_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.created_unix_nanos = reader.u64()?;
+    Ok(())
+}
+
+fn decode_v20_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.rollback = match reader.u8()? {
+        0 => false,
+        1 => true,
+        _ => return Err("archive contains invalid rollback flag"),
+    };
+    world.diverged = match reader.u8()? {
+        0 => false,
+        1 => true,
+        _ => return Err("archive contains invalid diverged flag"),
+    };
+    Ok(())
+}
+
+fn decode_v21_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.buoyancy = match reader.u8()? {
+        0 => None,
+        1 => {
+            let buoyancy = Buoyancy {
+                fluid_density: reader.f64()?,
+                object_volume: reader.f64()?,
+                fluid_top_y: reader.f64()?,
+            };
+            check(
+                buoyancy.fluid_density > 0.0 && buoyancy.object_volume > 0.0,
+                "archive contains invalid buoyancy",
+            )?;
+            Some(buoyancy)
+        }
+        _ => return Err("archive contains invalid buoyancy flag"),
+    };
+    Ok(())
+}
+
+fn decode_v23_fields(reader: &mut Reader, world: &mut World) -> Result<(), &'static str> {
+    world.energy_dissipated = reader.f64()?;
+    check(world.energy_dissipated >= 0.0, "archive contains negative dissipated energy")
+}
+
+// Links must join two distinct archived worlds.
+fn decode_links(
+    reader: &mut Reader,
+    worlds: &[(u64, World)],
+) -> Result<Vec<(u64, SpringLink)>, &'static str> {
+    let archived = |handle: u64| worlds.iter().any(|(h, _)| *h == handle);
+    let link_count = reader.len(40)?;
+    let mut links: Vec<(u64, SpringLink)> = Vec::with_capacity(link_count);
+    for _ in 0..link_count {
+        let id = reader.u64()?;
+        check(id != 0, "archive contains link id 0")?;
+        check(links.iter().all(|(i, _)| *i != id), "archive contains duplicate link id")?;
+        let link = SpringLink {
+            a: reader.u64()?,
+            b: reader.u64()?,
+            k: reader.f64()?,
+            rest_length: reader.f64()?,
+        };
+        check(
+            link.a != link.b && archived(link.a) && archived(link.b),
+            "archive contains link to an unknown world",
+        )?;
+        check(link.k >= 0.0 && link.rest_length >= 0.0, "archive contains invalid link")?;
+        links.push((id, link));
+    }
+    Ok(links)
+}
+
+pub(crate) type Decoded = (Vec<(u64, World)>, Vec<(u64, SpringLink)>);
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Decoded, &'static str> {
+    let mut reader = Reader { bytes };
+    if &reader.take::<4>()? != MAGIC {
+        return Err("not a world archive");
+    }
+    let version = reader.u32()?;
+    if version == 0 {
+        return Err("unsupported archive version");
+    }
+    if version > ARCHIVE_VERSION {
+        return Err("archive version is newer than this kernel");
+    }
+    let count = reader.len(40)?;
+    let mut worlds: Vec<(u64, World)> = Vec::with_capacity(count);
+    for _ in 0..count {
+        let handle = reader.u64()?;
+        if handle == 0 {
+            return Err("archive contains handle 0");
+        }
+        if worlds.iter().any(|(h, _)| *h == handle) {
+            return Err("archive contains duplicate handle");
+        }
+        let t = reader.f64()?;
+        let y = reader.f64()?;
+        let vy = reader.f64()?;
+        let g = reader.f64()?;
+        let mut world = World::new(y, vy, g);
+        world.t = t;
+        if version >= 2 {
+            decode_v2_fields(&mut reader, &mut world)?;
+        }
+        if version >= 3 {
+            decode_v3_fields(&mut reader, &mut world)?;
+        }
+        if version >= 4 {
+            decode_v4_fields(&mut reader, &mut world)?;
+        }
+        if version >= 5 {
+            decode_v5_fields(&mut reader, &mut world)?;
+        }
+        if version >= 6 {
+            decode_v6_fields(&mut reader, &mut world)?;
+        }
+        if version >= 7 {
+            decode_v7_fields(&mut reader, &mut world)?;
+        }
+        if version >= 8 {
+            decode_v8_fields(&mut reader, &mut world)?;
+        }
+        if version >= 9 {
+            decode_v9_fields(&mut reader, &mut world)?;
+        }
+        if version >= 10 {
+            decode_v10_fields(&mut reader, &mut world)?;
+        }
+        if version >= 11 {
+            decode_v11_fields(&mut reader, &mut world)?;
+        }
+        if version >= 12 {
+            decode_v12_fields(&mut reader, &mut world)?;
+        }
+        if version >= 13 {
+            decode_v13_fields(&mut reader, &mut world)?;
+        }
+        if version >= 14 {
+            decode_v14_fields(&mut reader, &mut world)?;
+        }
+        if version >= 15 {
+            decode_v15_fields(&mut reader, &mut world)?;
+        }
+        if version >= 16 {
+            decode_v16_fields(&mut reader, &mut world)?;
+        }
+        if version >= 17 {
+            decode_v17_fields(&mut reader, &mut world)?;
+        }
+        if version >= 18 {
+            decode_v18_fields(&mut reader, &mut world)?;
+        }
+        if version >= 19 {
+            decode_v19_fields(&mut reader, &mut world)?;
+        }
+        if version >= 20 {
+            decode_v20_fields(&mut reader, &mut world)?;
+        }
+        if version >= 21 {
+            decode_v21_fields(&mut reader, &mut world)?;
+        }
+        if version >= 23 {
+            decode_v23_fields(&mut reader, &mut world)?;
+        }
+        worlds.push((handle, world));
+    }
+    let links = if version >= 22 {
+        decode_links(&mut reader, &worlds)?
+    } else {
+        Vec::new()
+    };
+    if !reader.bytes.is_empty() {
+        return Err("archive has trailing bytes");
+    }
+    Ok((worlds, links))
+}
@@ -0,0 +1,71 @@
+// Extra bodies sharing a world's environment (gravity, bounds, clock) that
+// collide elastically with each other along y. The world's primary particle
+// does not take part in body collisions.
+
+use super::{wrap_into, BoundaryMode, Bounds};
+
+#[derive(Clone, Copy)]
+pub(crate) struct Body {
+    pub(crate) y: f64,
+    pub(crate) vy: f64,
+    pub(crate) mass: f64,
+    pub(crate) radius: f64,
+}
+
+// Keeps the body's surface inside the channel, handling contacts by the
+// world's boundary mode like the primary particle does.
+pub(crate) fn collide_bounds(body: &mut Body, bounds: &Bounds, mode: BoundaryMode) {
+    let e = bounds.restitution;
+    let low = bounds.floor_y + body.radius;
+    let high = bounds.ceiling_y - body.radius;
+    let outside = body.y < low || body.y > high;
+    match mode {
+        BoundaryMode::Reflect => {}
+        BoundaryMode::Clamp => {
+            if outside {
+                body.y = body.y.clamp(low, high);
+                body.vy = 0.0;
+            }
+            return;
+        }
+        BoundaryMode::Wrap => {
+            if outside && low < high {
+                body.y = wrap_into(body.y, low, high);
+            }
+            return;
+        }
+    }
+    if body.y < low {
+        body.y = low + (low - body.y) * e;
+        body.vy = body.vy.abs() * e;
+    } else if body.y > high {
+        body.y = high - (body.y - high) * e;
+        body.vy = -body.vy.abs() * e;
+    }
+}
+
+// Resolves contacts between neighbours in height order. Pairs are visited
+// bottom to top (ties broken by insertion index) and passes repeat until no
+// touching pair is still approaching, so simultaneous contacts always
+// resolve in the same order for the same inputs.
+pub(crate) fn resolve_collisions(bodies: &mut [Body]) {
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_by(|&a, &b| bodies[a].y.total_cmp(&bodies[b].y).then(a.cmp(&b)));
+    for _ in 0..bodies.len() {
+        let mut resolved_any = false;
+        for pair in order.windows(2) {
+            let (lo, hi) = (bodies[pair[0]], bodies[pair[1]]);
+            let touching = hi.y - lo.y <= lo.radius + hi.radius;
+            if !touching || lo.vy <= hi.vy {
+                continue;
+            }
+            let total = lo.mass + hi.mass;
+            bodies[pair[0]].vy = ((lo.mass - hi.mass) * lo.vy + 2.0 * hi.mass * hi.vy) / total;
+            bodies[pair[1]].vy = ((hi.mass - lo.mass) * hi.vy + 2.0 * lo.mass * lo.vy) / total;
+            resolved_any = true;
+        }
+        if !resolved_any {
+            break;
+        }
+    }
+}
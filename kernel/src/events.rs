@@ -0,0 +1,89 @@
+// Event-resolved stepping: integrate in short substeps and, when a substep
+// contains a ground crossing or an apex, bisect its length so the world stops
+// at the first step that reaches the event.
+
+use super::{advance, World};
+
+pub(crate) const EVENT_TIMEOUT: i32 = 0;
+pub(crate) const EVENT_GROUND: i32 = 1;
+pub(crate) const EVENT_APEX: i32 = 2;
+
+// Longest substep used while searching for events, and the most substeps a
+// single call may take (longer windows use proportionally longer substeps).
+const EVENT_SUBSTEP: f64 = 1e-3;
+const MAX_SUBSTEPS: f64 = 10_000.0;
+const BISECTION_ROUNDS: u32 = 60;
+
+// The ground is the floor bound when one is set, otherwise y = 0.
+fn ground(world: &World) -> f64 {
+    world.bounds.map_or(0.0, |b| b.floor_y)
+}
+
+// Which event, if any, a step of `h` from `world` passes. The probe runs
+// without bounds so a floor contact shows up as a crossing instead of being
+// reflected away. When one step passes both, ground wins: the kind depends
+// only on the state and `h`, so identical inputs give identical sequences.
+fn event_within(world: &World, h: f64) -> Option<i32> {
+    let mut probe = world.clone();
+    probe.bounds = None;
+    advance(&mut probe, h);
+    let floor = ground(world);
+    if world.y > floor && probe.y <= floor {
+        Some(EVENT_GROUND)
+    } else if world.vy > 0.0 && probe.vy <= 0.0 {
+        Some(EVENT_APEX)
+    } else {
+        None
+    }
+}
+
+/// Advances `world` by up to `window` seconds, stopping at the first ground
+/// or apex event. Returns the event kind (`EVENT_TIMEOUT` if none occurred).
+pub(crate) fn step_to_event(world: &mut World, window: f64) -> i32 {
+    let substep = EVENT_SUBSTEP.max(window / MAX_SUBSTEPS);
+    let end = world.t + window;
+    while world.t < end {
+        let h = substep.min(end - world.t);
+        if event_within(world, h).is_some() {
+            // Shrink to the shortest step that still reaches the event;
+            // `hi` always satisfies the predicate, so the kind is stable.
+            let (mut lo, mut hi) = (0.0, h);
+            for _ in 0..BISECTION_ROUNDS {
+                let mid = 0.5 * (lo + hi);
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                if event_within(world, mid).is_some() {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            let kind = event_within(world, hi).unwrap_or(EVENT_TIMEOUT);
+            advance(world, hi);
+            return kind;
+        }
+        advance(world, h);
+    }
+    EVENT_TIMEOUT
+}
+
+/// Traces the exact constant-gravity parabola backward from the current
+/// state to the most recent apex or ground (y = 0) crossing and returns the
+/// state there as `(y, vy, t)`; `None` if the past holds neither. Only
+/// meaningful for linear worlds.
+pub(crate) fn ballistic_origin(world: &World) -> Option<(f64, f64, f64)> {
+    let (y, vy, g) = (world.y, world.vy, world.g);
+    // Seconds back to each candidate, NaN where there is none; going back
+    // tau, the particle was at y - vy*tau - g*tau^2/2 moving at vy + g*tau.
+    let candidates = if g != 0.0 {
+        let root = (vy * vy + 2.0 * g * y).sqrt();
+        [-vy / g, (-vy + root) / g, (-vy - root) / g]
+    } else if vy != 0.0 {
+        [y / vy, f64::NAN, f64::NAN]
+    } else {
+        [f64::NAN; 3]
+    };
+    let tau = candidates.into_iter().filter(|&tau| tau >= 0.0).reduce(f64::min)?;
+    Some((y - vy * tau - 0.5 * g * tau * tau, vy + g * tau, world.t - tau))
+}
@@ -1,41 +1,407 @@
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+mod archive;
+mod bodies;
+mod events;
+mod proto;
+
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{LazyLock, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 const OK: i32 = 0;
 const INVALID_ARGUMENT: i32 = 1;
 const INVALID_HANDLE: i32 = 2;
 const POLICY_DENIED: i32 = 3;
 const INTERNAL_ERROR: i32 = 4;
+// Not an error: a world cursor has no more handles.
+const CURSOR_END: i32 = 5;
+const IO_ERROR: i32 = 6;
+// A registry step stopped at the world's time limit.
+const TIME_LIMIT_REACHED: i32 = 7;
+// A rollback-enabled world refused a step that left its state non-finite.
+const NUMERICAL_OVERFLOW: i32 = 8;
+
+// Error details refine the primary code; 0 means no further detail.
+const DETAIL_NONE: i32 = 0;
+const DETAIL_STEP_LIMIT: i32 = 1;
+const DETAIL_WORLD_LIMIT: i32 = 2;
+
+// Predicates for `pl_world_filter`.
+const FILTER_LANDED: i32 = 0;
+const FILTER_ASLEEP: i32 = 1;
+const FILTER_DIVERGED: i32 = 2;
+const FILTER_RISING: i32 = 3;
+
+// Units accepted by `pl_world_set_gravity_units`, and their SI factors.
+const GRAVITY_UNIT_MPS2: i32 = 0;
+const GRAVITY_UNIT_FTPS2: i32 = 1;
+const GRAVITY_UNIT_STANDARD_G: i32 = 2;
+const METERS_PER_FOOT: f64 = 0.3048;
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+// Force terms reported by `pl_world_dominant_force`.
+const FORCE_GRAVITY: i32 = 0;
+const FORCE_REGIONS: i32 = 1;
+const FORCE_BUOYANCY: i32 = 2;
 
 const MAX_STEPS: u32 = 10_000;
+// Magnitudes below this snap to zero in worlds with denormal flushing.
+const DENORMAL_FLUSH_EPSILON: f64 = 1e-300;
 const G: f64 = 9.81;
 
-struct World {
+/// One simulated world: a primary particle plus any extra bodies. Registry
+/// worlds live behind handles; this type is exposed so the integrator can be
+/// driven (e.g. benchmarked) directly.
+#[derive(Clone)]
+pub struct World {
     t: f64,
     y: f64,
     vy: f64,
+    g: f64,
+    noise_amplitude: f64,
+    rng_state: u64,
+    // (altitude, g) pairs sorted by strictly increasing altitude; when
+    // non-empty this replaces `g`.
+    gravity_table: Vec<(f64, f64)>,
+    bounds: Option<Bounds>,
+    last_step_nanos: u64,
+    // Wall-clock creation time in nanoseconds since the Unix epoch; kept
+    // across snapshots so restored worlds keep their age.
+    created_unix_nanos: u64,
+    measurement: Measurement,
+    step_count: u64,
+    time_scale: f64,
+    restitution_jitter: f64,
+    jitter_rng_state: u64,
+    bodies: Vec<bodies::Body>,
+    flush_denormals: bool,
+    contact_epsilon: f64,
+    // Largest |vy| of the primary particle seen so far, including the
+    // initial velocity and pre-bounce impact speeds.
+    max_speed: f64,
+    // Simulated time registry stepping never passes; infinite when unset.
+    time_limit: f64,
+    // Added to `t` in every reported state; integration, limits and
+    // schedules use the raw `t`.
+    time_offset: f64,
+    boundary_mode: BoundaryMode,
+    // Pending (at_t, g) changes in time order (ties keep scheduling order);
+    // each replaces `g` once a step ends at or after `at_t`.
+    gravity_schedule: Vec<(f64, f64)>,
+    // (y_low, y_high, extra_accel) bands in insertion order; every band
+    // containing the particle's y in [y_low, y_high) adds its acceleration.
+    regions: Vec<(f64, f64, f64)>,
+    buoyancy: Option<Buoyancy>,
+    // Simulated time of the latest floor contact; 0 until the first one so
+    // the airborne time of a world that never landed is its total time.
+    last_contact_t: f64,
+    // Kinetic energy (unit mass) removed by floor contacts since creation:
+    // inelastic bounces, clamping and settling.
+    energy_dissipated: f64,
+    // Set by the first step that leaves the particle's state non-finite and
+    // never cleared. Persisted, since a rollback world keeps finite state
+    // after diverging and can still be archived; not compared.
+    diverged: bool,
+    // Checkpoint every `pl_world_step` / `pl_world_step_get` step and undo
+    // one that leaves the particle's state non-finite.
+    rollback: bool,
+    // Outcome of the latest call that resolved this world: None after a
+    // success. Not persisted or compared.
+    last_error: Option<(i32, String)>,
+}
+
+// Gaussian noise added to reported state only; the integrated state is
+// never perturbed. Uses its own stream so it cannot disturb force noise.
+#[derive(Clone, Copy)]
+struct Measurement {
+    y_sigma: f64,
+    vy_sigma: f64,
+    rng_state: u64,
+}
+
+// How a bound contact is resolved.
+#[derive(Clone, Copy, PartialEq)]
+enum BoundaryMode {
+    // Bounce back scaled by the restitution.
+    Reflect,
+    // Stop on the bound with zero velocity.
+    Clamp,
+    // Re-enter from the opposite bound keeping velocity (periodic domain).
+    Wrap,
+}
+
+impl BoundaryMode {
+    fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(BoundaryMode::Reflect),
+            1 => Some(BoundaryMode::Clamp),
+            2 => Some(BoundaryMode::Wrap),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            BoundaryMode::Reflect => 0,
+            BoundaryMode::Clamp => 1,
+            BoundaryMode::Wrap => 2,
+        }
+    }
+}
+
+// A fluid column the primary particle (unit mass) is immersed in below
+// `fluid_top_y`.
+#[derive(Clone, Copy)]
+struct Buoyancy {
+    fluid_density: f64,
+    object_volume: f64,
+    fluid_top_y: f64,
+}
+
+#[derive(Clone, Copy)]
+struct Bounds {
+    floor_y: f64,
+    ceiling_y: f64,
+    restitution: f64,
+}
+
+impl World {
+    pub fn new(y: f64, vy: f64, g: f64) -> Self {
+        World {
+            t: 0.0,
+            y,
+            vy,
+            g,
+            noise_amplitude: 0.0,
+            rng_state: 0,
+            gravity_table: Vec::new(),
+            bounds: None,
+            last_step_nanos: 0,
+            created_unix_nanos: unix_nanos_now(),
+            measurement: Measurement {
+                y_sigma: 0.0,
+                vy_sigma: 0.0,
+                rng_state: 0,
+            },
+            step_count: 0,
+            time_scale: 1.0,
+            restitution_jitter: 0.0,
+            jitter_rng_state: 0,
+            bodies: Vec::new(),
+            flush_denormals: false,
+            contact_epsilon: 0.0,
+            max_speed: vy.abs(),
+            time_limit: f64::INFINITY,
+            time_offset: 0.0,
+            boundary_mode: BoundaryMode::Reflect,
+            gravity_schedule: Vec::new(),
+            regions: Vec::new(),
+            buoyancy: None,
+            last_contact_t: 0.0,
+            energy_dissipated: 0.0,
+            diverged: false,
+            rollback: false,
+            last_error: None,
+        }
+    }
+
+    pub fn state(&self) -> PlState {
+        PlState {
+            t: self.t,
+            y: self.y,
+            vy: self.vy,
+        }
+    }
+
+    /// True when the primary particle moves under constant gravity alone, so
+    /// closed-form ballistic answers match stepping (up to integration
+    /// error). Measurement noise and time scale do not affect this.
+    pub fn is_linear(&self) -> bool {
+        self.gravity_table.is_empty()
+            && self.gravity_schedule.is_empty()
+            && self.regions.is_empty()
+            && self.buoyancy.is_none()
+            && self.noise_amplitude == 0.0
+            && self.bounds.is_none()
+    }
+
+    // Real-valued fields must agree within `tol`; counts, random stream
+    // positions, flags and list lengths must match exactly. Step timing and
+    // creation time are ignored.
+    fn approx_eq(&self, other: &World, tol: f64) -> bool {
+        let close = |a: f64, b: f64| a == b || (a - b).abs() <= tol;
+        let all_close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(&x, &y)| close(x, y));
+        let bounds_eq = match (self.bounds, other.bounds) {
+            (None, None) => true,
+            (Some(a), Some(b)) => {
+                all_close(&[a.floor_y, a.ceiling_y, a.restitution], &[b.floor_y, b.ceiling_y, b.restitution])
+            }
+            _ => false,
+        };
+        let buoyancy_eq = match (self.buoyancy, other.buoyancy) {
+            (None, None) => true,
+            (Some(a), Some(b)) => all_close(
+                &[a.fluid_density, a.object_volume, a.fluid_top_y],
+                &[b.fluid_density, b.object_volume, b.fluid_top_y],
+            ),
+            _ => false,
+        };
+        let (ma, mb) = (self.measurement, other.measurement);
+        all_close(
+            &[
+                self.t,
+                self.y,
+                self.vy,
+                self.g,
+                self.noise_amplitude,
+                self.time_scale,
+                self.restitution_jitter,
+                self.contact_epsilon,
+                self.max_speed,
+                self.time_limit,
+                self.time_offset,
+                self.last_contact_t,
+                self.energy_dissipated,
+                ma.y_sigma,
+                ma.vy_sigma,
+            ],
+            &[
+                other.t,
+                other.y,
+                other.vy,
+                other.g,
+                other.noise_amplitude,
+                other.time_scale,
+                other.restitution_jitter,
+                other.contact_epsilon,
+                other.max_speed,
+                other.time_limit,
+                other.time_offset,
+                other.last_contact_t,
+                other.energy_dissipated,
+                mb.y_sigma,
+                mb.vy_sigma,
+            ],
+        ) && bounds_eq
+            && buoyancy_eq
+            && self.step_count == other.step_count
+            && (self.rng_state, ma.rng_state, self.jitter_rng_state)
+                == (other.rng_state, mb.rng_state, other.jitter_rng_state)
+            && self.flush_denormals == other.flush_denormals
+            && self.rollback == other.rollback
+            && self.boundary_mode == other.boundary_mode
+            && self.gravity_schedule.len() == other.gravity_schedule.len()
+            && self
+                .gravity_schedule
+                .iter()
+                .zip(&other.gravity_schedule)
+                .all(|(a, b)| close(a.0, b.0) && close(a.1, b.1))
+            && self.regions.len() == other.regions.len()
+            && self
+                .regions
+                .iter()
+                .zip(&other.regions)
+                .all(|(a, b)| all_close(&[a.0, a.1, a.2], &[b.0, b.1, b.2]))
+            && self.gravity_table.len() == other.gravity_table.len()
+            && self
+                .gravity_table
+                .iter()
+                .zip(&other.gravity_table)
+                .all(|(a, b)| close(a.0, b.0) && close(a.1, b.1))
+            && self.bodies.len() == other.bodies.len()
+            && self
+                .bodies
+                .iter()
+                .zip(&other.bodies)
+                .all(|(a, b)| all_close(&[a.y, a.vy, a.mass, a.radius], &[b.y, b.vy, b.mass, b.radius]))
+    }
+}
+
+#[repr(C)]
+pub struct PlWorldConfig {
+    pub y0: f64,
+    pub vy0: f64,
+    pub g: f64,
+    pub noise_amplitude: f64,
+}
+
+#[repr(C)]
+pub struct PlState {
+    pub t: f64,
+    pub y: f64,
+    pub vy: f64,
 }
 
 struct LastError {
     code: i32,
+    detail: i32,
     message: String,
 }
 
+type LogCallback = extern "C" fn(code: i32, msg: *const u8, len: u32);
+type AccelCallback = extern "C" fn(t: f64, y: f64, vy: f64, user: *mut c_void) -> f64;
+type CondCallback = extern "C" fn(t: f64, y: f64, vy: f64, user: *mut c_void) -> i32;
+type StreamCallback =
+    extern "C" fn(ts: *const f64, ys: *const f64, vys: *const f64, n: u32, user: *mut c_void);
+
+struct Cursor {
+    handles: Vec<u64>,
+    next: usize,
+}
+
+// A spring between the primary particles of two worlds (unit masses).
+#[derive(Clone, Copy)]
+struct SpringLink {
+    a: u64,
+    b: u64,
+    k: f64,
+    rest_length: f64,
+}
+
+static LINK_COUNTER: AtomicU64 = AtomicU64::new(1);
+static LINKS: Mutex<BTreeMap<u64, SpringLink>> = Mutex::new(BTreeMap::new());
+static CURSOR_COUNTER: AtomicU64 = AtomicU64::new(1);
+static CURSORS: Mutex<BTreeMap<u64, Cursor>> = Mutex::new(BTreeMap::new());
 static HANDLE_COUNTER: AtomicU64 = AtomicU64::new(1);
-static WORLDS: LazyLock<Mutex<HashMap<u64, World>>> =
-    LazyLock::new(|| Mutex::new(HashMap::new()));
+static DEFAULT_GRAVITY: AtomicU64 = AtomicU64::new(G.to_bits());
+// Most live worlds allowed at once; 0 means unlimited.
+static MAX_WORLDS: AtomicU32 = AtomicU32::new(0);
+// Integration steps taken by every world (including probe copies) since
+// startup or the last test reset. Monitoring only, so relaxed.
+static TOTAL_STEPS: AtomicU64 = AtomicU64::new(0);
+// Ordered by handle so every multi-world walk (list, archive) is deterministic.
+static WORLDS: LazyLock<Mutex<BTreeMap<u64, World>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+static LOG_CALLBACK: Mutex<Option<LogCallback>> = Mutex::new(None);
 static LAST_ERROR: LazyLock<Mutex<LastError>> = LazyLock::new(|| {
     Mutex::new(LastError {
         code: OK,
+        detail: DETAIL_NONE,
         message: String::new(),
     })
 });
 
 fn set_error(code: i32, message: impl Into<String>) -> i32 {
+    set_error_detail(code, DETAIL_NONE, message)
+}
+
+fn set_error_detail(code: i32, detail: i32, message: impl Into<String>) -> i32 {
+    let message = message.into();
+    if code != OK {
+        // Copy the callback out so it runs without holding the callback lock.
+        let callback = LOG_CALLBACK.lock().ok().and_then(|cb| *cb);
+        if let Some(callback) = callback {
+            callback(code, message.as_ptr(), message.len() as u32);
+        }
+    }
     if let Ok(mut err) = LAST_ERROR.lock() {
         err.code = code;
-        err.message = message.into();
+        err.detail = detail;
+        err.message = message;
     }
     code
 }
@@ -54,22 +420,537 @@ fn validate_dt(dt: f64) -> Result<(), i32> {
     Ok(())
 }
 
+fn validate_gravity(g: f64) -> Result<(), i32> {
+    if !g.is_finite() {
+        return Err(set_error(INVALID_ARGUMENT, "gravity must be finite"));
+    }
+    Ok(())
+}
+
+fn validate_config(cfg: &PlWorldConfig) -> Result<(), i32> {
+    if !cfg.y0.is_finite() || !cfg.vy0.is_finite() {
+        return Err(set_error(INVALID_ARGUMENT, "y0 and vy0 must be finite"));
+    }
+    validate_gravity(cfg.g)?;
+    if !cfg.noise_amplitude.is_finite() || cfg.noise_amplitude < 0.0 {
+        return Err(set_error(INVALID_ARGUMENT, "amplitude must be finite and >= 0"));
+    }
+    Ok(())
+}
+
 fn validate_steps(steps: u32) -> Result<(), i32> {
     if steps == 0 {
         return Err(set_error(INVALID_ARGUMENT, "steps must be > 0"));
     }
     if steps > MAX_STEPS {
-        return Err(set_error(POLICY_DENIED, "steps exceeds limit"));
+        return Err(set_error_detail(POLICY_DENIED, DETAIL_STEP_LIMIT, "steps exceeds limit"));
     }
     Ok(())
 }
 
-fn world_map() -> Result<std::sync::MutexGuard<'static, HashMap<u64, World>>, i32> {
+// SplitMix64: tiny, seedable with any value (including 0) and fully
+// deterministic, which is all per-world noise needs.
+fn next_u64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Uniform in [-1, 1).
+fn next_signed_unit(state: &mut u64) -> f64 {
+    let unit = (next_u64(state) >> 11) as f64 / (1u64 << 53) as f64;
+    2.0 * unit - 1.0
+}
+
+// Standard normal via Box-Muller.
+fn next_gaussian(state: &mut u64) -> f64 {
+    let u1 = ((next_u64(state) >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    let u2 = (next_u64(state) >> 11) as f64 / (1u64 << 53) as f64;
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+// Current wall-clock time in nanoseconds since the Unix epoch.
+fn unix_nanos_now() -> u64 {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    u64::try_from(since_epoch.as_nanos()).unwrap_or(u64::MAX)
+}
+
+// The integrated state on the world's offset clock.
+fn reported_state(world: &World) -> PlState {
+    let mut state = world.state();
+    state.t += world.time_offset;
+    state
+}
+
+// State as a caller observes it: the true state plus any measurement noise.
+fn measured_state(world: &mut World) -> PlState {
+    let mut state = reported_state(world);
+    let m = &mut world.measurement;
+    if m.y_sigma > 0.0 {
+        state.y += m.y_sigma * next_gaussian(&mut m.rng_state);
+    }
+    if m.vy_sigma > 0.0 {
+        state.vy += m.vy_sigma * next_gaussian(&mut m.rng_state);
+    }
+    state
+}
+
+fn gravity_at(world: &World, y: f64) -> f64 {
+    let table = &world.gravity_table;
+    let (first, last) = match (table.first(), table.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return world.g,
+    };
+    if y <= first.0 {
+        return first.1;
+    }
+    if y >= last.0 {
+        return last.1;
+    }
+    let upper = table.partition_point(|&(altitude, _)| altitude <= y);
+    let (y0, g0) = table[upper - 1];
+    let (y1, g1) = table[upper];
+    g0 + (g1 - g0) * ((y - y0) / (y1 - y0))
+}
+
+// Gravitational potential per unit mass: the integral of g from 0 to `y`,
+// with the gravity table (clamped past its ends) when one is set. g is linear
+// between knots, so the trapezoid rule over them is exact.
+fn potential_at(world: &World, y: f64) -> f64 {
+    let (lo, hi) = if y < 0.0 { (y, 0.0) } else { (0.0, y) };
+    let mut knots = vec![lo];
+    knots.extend(world.gravity_table.iter().map(|&(altitude, _)| altitude).filter(|&a| a > lo && a < hi));
+    knots.push(hi);
+    let integral: f64 = knots
+        .windows(2)
+        .map(|pair| 0.5 * (gravity_at(world, pair[0]) + gravity_at(world, pair[1])) * (pair[1] - pair[0]))
+        .sum();
+    if y < 0.0 {
+        -integral
+    } else {
+        integral
+    }
+}
+
+// Largest restoring stiffness (1/s^2) the gravity table applies: where g
+// grows with altitude, a displaced particle feels a spring of constant dg/dy.
+// Constant gravity, noise and contacts add no stiffness.
+fn max_stiffness(world: &World) -> f64 {
+    world
+        .gravity_table
+        .windows(2)
+        .map(|pair| (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0))
+        .fold(0.0, f64::max)
+}
+
+// Sum of the extra accelerations of every region containing `y`.
+fn region_accel(world: &World, y: f64) -> f64 {
+    world
+        .regions
+        .iter()
+        .filter(|&&(low, high, _)| low <= y && y < high)
+        .map(|&(_, _, accel)| accel)
+        .sum()
+}
+
+// Upward acceleration of the displaced fluid's weight on the unit-mass
+// particle, fluid_density * object_volume * g(y), while `y` is below the
+// fluid surface; 0 above it or without a fluid.
+fn buoyancy_accel(world: &World, y: f64) -> f64 {
+    match world.buoyancy {
+        Some(b) if y < b.fluid_top_y => b.fluid_density * b.object_volume * gravity_at(world, y),
+        _ => 0.0,
+    }
+}
+
+// Gravity, force regions and buoyancy at the current state: the part of
+// `advance_with_accel`'s acceleration that involves no random draw.
+fn deterministic_accel(world: &World) -> f64 {
+    -gravity_at(world, world.y) + region_accel(world, world.y) + buoyancy_accel(world, world.y)
+}
+
+fn advance(world: &mut World, dt: f64) {
+    advance_with_accel(world, dt, 0.0);
+}
+
+// One step with an extra external acceleration (e.g. spring links) added to
+// the world's own force model. Returns `integrate`'s trace.
+fn advance_with_accel(world: &mut World, dt: f64, extra: f64) -> (f64, f64) {
+    let mut accel = -gravity_at(world, world.y);
+    if !world.regions.is_empty() {
+        accel += region_accel(world, world.y);
+    }
+    if world.buoyancy.is_some() {
+        accel += buoyancy_accel(world, world.y);
+    }
+    if world.noise_amplitude > 0.0 {
+        accel += world.noise_amplitude * next_signed_unit(&mut world.rng_state);
+    }
+    if extra != 0.0 {
+        accel += extra;
+    }
+    integrate(world, dt, accel)
+}
+
+// Applies one step of `accel` to the particle, then contacts, bodies and
+// flushing; shared by the built-in force model and host-supplied ones.
+// Returns the particle's `(vy, y)` right after the kick and drift, before
+// anything else touches them.
+//
+// Results must be bit-identical across platforms. Keep each product and sum
+// a separate IEEE operation: rustc never contracts `a * b + c` into an FMA on
+// its own, so never introduce `mul_add` or fast-math style intrinsics here.
+fn integrate(world: &mut World, dt: f64, accel: f64) -> (f64, f64) {
+    world.vy += accel * dt;
+    world.y += world.vy * dt;
+    let trace = (world.vy, world.y);
+    world.t += dt;
+    world.step_count += 1;
+    TOTAL_STEPS.fetch_add(1, Ordering::Relaxed);
+    if !world.gravity_schedule.is_empty() {
+        apply_gravity_schedule(world);
+    }
+    world.max_speed = world.max_speed.max(world.vy.abs());
+    if let Some(bounds) = world.bounds {
+        collide(world, &bounds);
+        if world.contact_epsilon > 0.0 && world.boundary_mode == BoundaryMode::Reflect {
+            settle(world, &bounds);
+        }
+    }
+    if !world.bodies.is_empty() {
+        advance_bodies(world, dt);
+    }
+    if world.flush_denormals {
+        flush_tiny(world);
+    }
+    if !(world.y.is_finite() && world.vy.is_finite()) {
+        world.diverged = true;
+    }
+    trace
+}
+
+fn apply_gravity_schedule(world: &mut World) {
+    let due = world.gravity_schedule.partition_point(|&(at_t, _)| at_t <= world.t);
+    if let Some(&(_, g)) = world.gravity_schedule[..due].last() {
+        world.g = g;
+    }
+    world.gravity_schedule.drain(..due);
+}
+
+fn flush_value(value: &mut f64) {
+    if value.abs() < DENORMAL_FLUSH_EPSILON {
+        *value = 0.0;
+    }
+}
+
+fn flush_tiny(world: &mut World) {
+    flush_value(&mut world.y);
+    flush_value(&mut world.vy);
+    for body in &mut world.bodies {
+        flush_value(&mut body.y);
+        flush_value(&mut body.vy);
+    }
+}
+
+fn advance_bodies(world: &mut World, dt: f64) {
+    let mut bodies = std::mem::take(&mut world.bodies);
+    for body in &mut bodies {
+        body.vy -= gravity_at(world, body.y) * dt;
+        body.y += body.vy * dt;
+        if let Some(bounds) = world.bounds {
+            bodies::collide_bounds(body, &bounds, world.boundary_mode);
+        }
+    }
+    bodies::resolve_collisions(&mut bodies);
+    world.bodies = bodies;
+}
+
+// Restitution for one contact: the base value, perturbed by the seeded
+// jitter stream when configured, clamped to [0, 1].
+fn contact_restitution(world: &mut World, bounds: &Bounds) -> f64 {
+    if world.restitution_jitter == 0.0 {
+        return bounds.restitution;
+    }
+    let factor = 1.0 + world.restitution_jitter * next_signed_unit(&mut world.jitter_rng_state);
+    (bounds.restitution * factor).clamp(0.0, 1.0)
+}
+
+// Resolves a bound crossing by the world's boundary mode. Reflect mirrors
+// the penetration back inside, scaling both the overshoot and the velocity
+// by the restitution.
+fn collide(world: &mut World, bounds: &Bounds) {
+    let outside = world.y < bounds.floor_y || world.y > bounds.ceiling_y;
+    match world.boundary_mode {
+        BoundaryMode::Reflect => {}
+        BoundaryMode::Clamp => {
+            if world.y < bounds.floor_y {
+                world.last_contact_t = world.t;
+                world.energy_dissipated += 0.5 * world.vy * world.vy;
+            }
+            if outside {
+                world.y = world.y.clamp(bounds.floor_y, bounds.ceiling_y);
+                world.vy = 0.0;
+            }
+            return;
+        }
+        BoundaryMode::Wrap => {
+            if outside {
+                world.y = wrap_into(world.y, bounds.floor_y, bounds.ceiling_y);
+            }
+            return;
+        }
+    }
+    if world.y < bounds.floor_y {
+        world.last_contact_t = world.t;
+        let e = contact_restitution(world, bounds);
+        world.y = bounds.floor_y + (bounds.floor_y - world.y) * e;
+        world.energy_dissipated += 0.5 * world.vy * world.vy * (1.0 - e * e);
+        world.vy = world.vy.abs() * e;
+    } else if world.y > bounds.ceiling_y {
+        let e = contact_restitution(world, bounds);
+        world.y = bounds.ceiling_y - (world.y - bounds.ceiling_y) * e;
+        world.vy = -world.vy.abs() * e;
+    }
+}
+
+// Maps `y` into `[low, high)` with period `high - low`.
+fn wrap_into(y: f64, low: f64, high: f64) -> f64 {
+    low + (y - low).rem_euclid(high - low)
+}
+
+// Puts the particle at rest on the floor when it is within the contact
+// epsilon and slower than the speed gained falling that far, so a nearly
+// spent bounce cannot chatter around the floor.
+fn settle(world: &mut World, bounds: &Bounds) {
+    let g = gravity_at(world, bounds.floor_y);
+    let eps = world.contact_epsilon;
+    if g > 0.0 && world.y - bounds.floor_y < eps && world.vy.abs() < (2.0 * g * eps).sqrt() {
+        world.y = bounds.floor_y;
+        world.energy_dissipated += 0.5 * world.vy * world.vy;
+        world.vy = 0.0;
+        world.last_contact_t = world.t;
+    }
+}
+
+/// Advances `world` by `steps` steps of `dt` with no validation, locking or
+/// error reporting; `pl_world_step` validates its arguments and then calls
+/// this under the registry lock.
+pub fn step_world(world: &mut World, dt: f64, steps: u32) {
+    for _ in 0..steps {
+        advance(world, dt);
+    }
+}
+
+// One step that respects the world's time limit: a step that would pass the
+// limit is shortened to land exactly on it. Returns false once the world is
+// at its limit, in which case it must not be stepped further.
+fn advance_limited(world: &mut World, dt: f64, extra: f64) -> bool {
+    let left = world.time_limit - world.t;
+    if left <= 0.0 {
+        return false;
+    }
+    if dt <= left {
+        advance_with_accel(world, dt, extra);
+        world.t = world.t.min(world.time_limit);
+        return true;
+    }
+    advance_with_accel(world, left, extra);
+    world.t = world.time_limit;
+    false
+}
+
+// The deterministic acceleration (as `pl_world_net_acceleration` reports
+// it), except that a reflecting or clamping bound the particle rests on
+// supports it, so acceleration into that bound counts as zero.
+fn supported_accel(world: &World) -> f64 {
+    let accel = deterministic_accel(world);
+    if let Some(b) = world.bounds {
+        let supported = (world.y <= b.floor_y && accel < 0.0) || (world.y >= b.ceiling_y && accel > 0.0);
+        if supported && world.boundary_mode != BoundaryMode::Wrap {
+            return 0.0;
+        }
+    }
+    accel
+}
+
+fn at_rest(world: &World, v_eps: f64, a_eps: f64) -> bool {
+    world.vy.abs() < v_eps && supported_accel(world).abs() < a_eps
+}
+
+// Resting on the floor bound, however it got there (a bounce that settled,
+// or `pl_world_set_bounds` placing it).
+fn landed(world: &World) -> bool {
+    let on_floor = world.bounds.is_some_and(|b| world.y <= b.floor_y);
+    on_floor && world.vy == 0.0 && supported_accel(world) == 0.0
+}
+
+// Registry stepping: applies the world's time scale to the caller's logical
+// `dt` (a scale of 0 pauses), stops at the time limit, and times only the
+// integration, not validation or locking. Returns true if the limit stopped
+// stepping.
+fn step_timed(world: &mut World, dt: f64, steps: u32) -> bool {
+    let start = Instant::now();
+    let mut limited = false;
+    if world.time_scale > 0.0 {
+        let dt = dt * world.time_scale;
+        if world.time_limit == f64::INFINITY {
+            step_world(world, dt, steps);
+        } else {
+            limited = !(0..steps).all(|_| advance_limited(world, dt, 0.0));
+        }
+    }
+    world.last_step_nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    limited
+}
+
+// Runs a stepping call's integration, recording its wall-clock cost as the
+// world's last step time.
+fn timed<T>(world: &mut World, step: impl FnOnce(&mut World) -> T) -> T {
+    let start = Instant::now();
+    let result = step(world);
+    world.last_step_nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    result
+}
+
+// Runs one step of a stepping call under the world's rollback setting. With
+// rollback enabled the step runs on a checkpoint, and one that leaves the
+// particle's state non-finite is undone (the world is still marked
+// diverged) and returns `Err`.
+fn checked_step<T>(world: &mut World, step: impl FnOnce(&mut World) -> T) -> Result<T, ()> {
+    if !world.rollback {
+        return Ok(step(world));
+    }
+    let checkpoint = world.clone();
+    let result = step(world);
+    if !(world.y.is_finite() && world.vy.is_finite()) {
+        *world = checkpoint;
+        world.diverged = true;
+        return Err(());
+    }
+    Ok(result)
+}
+
+// `step_timed` for the live-world entry points, stopping with `Err` at the
+// first step rollback undoes.
+fn step_live(world: &mut World, dt: f64, steps: u32) -> Result<bool, ()> {
+    if !world.rollback {
+        return Ok(step_timed(world, dt, steps));
+    }
+    let start = Instant::now();
+    let mut outcome = Ok(false);
+    if world.time_scale > 0.0 {
+        let dt = dt * world.time_scale;
+        for _ in 0..steps {
+            match checked_step(world, |world| advance_limited(world, dt, 0.0)) {
+                Ok(true) => {}
+                Ok(false) => {
+                    outcome = Ok(true);
+                    break;
+                }
+                Err(()) => {
+                    outcome = Err(());
+                    break;
+                }
+            }
+        }
+    }
+    world.last_step_nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    outcome
+}
+
+const OVERFLOW_MESSAGE: &str = "step produced non-finite state and was rolled back";
+
+const TIME_LIMIT_MESSAGE: &str = "world reached its time limit";
+
+fn time_limit_error(handle: u64) -> i32 {
+    set_world_error(handle, TIME_LIMIT_REACHED, TIME_LIMIT_MESSAGE)
+}
+
+// Writes each requested component; null outputs are skipped.
+fn write_state_partial(state: &PlState, out_t: *mut f64, out_y: *mut f64, out_vy: *mut f64) {
+    for (out, value) in [(out_t, state.t), (out_y, state.y), (out_vy, state.vy)] {
+        if !out.is_null() {
+            unsafe {
+                *out = value;
+            }
+        }
+    }
+}
+
+fn world_map() -> Result<std::sync::MutexGuard<'static, BTreeMap<u64, World>>, i32> {
     WORLDS
         .lock()
         .map_err(|_| set_error(INTERNAL_ERROR, "failed to lock worlds"))
 }
 
+// Resolving a world resets its per-world error; a failure after resolution
+// is then recorded with `set_world_error`.
+fn with_world<T>(handle: u64, f: impl FnOnce(&mut World) -> T) -> Result<T, i32> {
+    let mut worlds = world_map()?;
+    match worlds.get_mut(&handle) {
+        Some(world) => {
+            world.last_error = None;
+            Ok(f(world))
+        }
+        None => Err(set_error(INVALID_HANDLE, "unknown handle")),
+    }
+}
+
+// Records an error on the world as well as globally. Must not be called
+// while the registry lock is held.
+fn set_world_error(handle: u64, code: i32, message: impl Into<String>) -> i32 {
+    set_world_error_detail(handle, code, DETAIL_NONE, message)
+}
+
+fn set_world_error_detail(handle: u64, code: i32, detail: i32, message: impl Into<String>) -> i32 {
+    let message = message.into();
+    if let Ok(mut worlds) = WORLDS.lock() {
+        if let Some(world) = worlds.get_mut(&handle) {
+            world.last_error = Some((code, message.clone()));
+        }
+    }
+    set_error_detail(code, detail, message)
+}
+
+/// Drops every world, restarts handle allocation at 1, restores the default
+/// gravity, lifts the world limit, removes the log callback, drops open cursors and spring links,
+/// zeroes the total step counter and clears the last error. Test-only: must not be called while
+/// any other thread is using the kernel.
+#[doc(hidden)]
+#[no_mangle]
+pub extern "C" fn pl_reset_for_testing() {
+    if let Ok(mut worlds) = WORLDS.lock() {
+        worlds.clear();
+        HANDLE_COUNTER.store(1, Ordering::SeqCst);
+    }
+    DEFAULT_GRAVITY.store(G.to_bits(), Ordering::SeqCst);
+    MAX_WORLDS.store(0, Ordering::SeqCst);
+    TOTAL_STEPS.store(0, Ordering::Relaxed);
+    if let Ok(mut slot) = LOG_CALLBACK.lock() {
+        *slot = None;
+    }
+    if let Ok(mut cursors) = CURSORS.lock() {
+        cursors.clear();
+    }
+    if let Ok(mut links) = LINKS.lock() {
+        links.clear();
+    }
+    clear_error();
+}
+
+/// Installs a sink that receives every non-OK error as it is recorded
+/// (`msg` is UTF-8, not NUL-terminated, valid only during the call); null
+/// disables it. The callback may run while kernel locks are held, so it must
+/// not call back into the kernel.
+#[no_mangle]
+pub extern "C" fn pl_set_log_callback(cb: Option<LogCallback>) {
+    if let Ok(mut slot) = LOG_CALLBACK.lock() {
+        *slot = cb;
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn pl_last_error_code() -> i32 {
     LAST_ERROR
@@ -79,11 +960,16 @@ pub extern "C" fn pl_last_error_code() -> i32 {
 }
 
 #[no_mangle]
-pub extern "C" fn pl_last_error_message(out_buf: *mut u8, buf_len: u32) -> u32 {
-    let msg = LAST_ERROR
+pub extern "C" fn pl_last_error_detail() -> i32 {
+    LAST_ERROR
         .lock()
-        .map(|err| err.message.clone())
-        .unwrap_or_else(|_| "failed to lock error".to_string());
+        .map(|err| err.detail)
+        .unwrap_or(DETAIL_NONE)
+}
+
+// Copies as much of `msg` as fits, always NUL-terminating, and returns its
+// full length so callers can size a buffer.
+fn write_message(msg: &str, out_buf: *mut u8, buf_len: u32) -> u32 {
     let bytes = msg.as_bytes();
     let needed = bytes.len() as u32;
     if out_buf.is_null() || buf_len == 0 {
@@ -98,95 +984,2311 @@ pub extern "C" fn pl_last_error_message(out_buf: *mut u8, buf_len: u32) -> u32 {
 }
 
 #[no_mangle]
-pub extern "C" fn pl_world_create(y0: f64, vy0: f64) -> u64 {
-    if !y0.is_finite() || !vy0.is_finite() {
-        set_error(INVALID_ARGUMENT, "y0 and vy0 must be finite");
-        return 0;
-    }
-    clear_error();
-    let handle = HANDLE_COUNTER.fetch_add(1, Ordering::SeqCst);
-    let world = World { t: 0.0, y: y0, vy: vy0 };
-    match WORLDS.lock() {
-        Ok(mut map) => {
-            map.insert(handle, world);
-            handle
-        }
-        Err(_) => {
-            set_error(INTERNAL_ERROR, "failed to lock worlds");
-            0
-        }
-    }
+pub extern "C" fn pl_last_error_message(out_buf: *mut u8, buf_len: u32) -> u32 {
+    let msg = LAST_ERROR
+        .lock()
+        .map(|err| err.message.clone())
+        .unwrap_or_else(|_| "failed to lock error".to_string());
+    write_message(&msg, out_buf, buf_len)
 }
 
+/// Byte length of the last error message, excluding the NUL terminator that
+/// `pl_last_error_message` appends; the same value a null/0 probe returns.
 #[no_mangle]
-pub extern "C" fn pl_world_destroy(handle: u64) {
-    if handle == 0 {
-        set_error(INVALID_HANDLE, "invalid handle");
-        return;
-    }
-    match WORLDS.lock() {
-        Ok(mut map) => {
-            if map.remove(&handle).is_some() {
-                clear_error();
-            } else {
-                set_error(INVALID_HANDLE, "unknown handle");
-            }
-        }
-        Err(_) => {
-            set_error(INTERNAL_ERROR, "failed to lock worlds");
-        }
+pub extern "C" fn pl_last_error_message_len() -> u32 {
+    pl_last_error_message(std::ptr::null_mut(), 0)
+}
+
+// Reads a world's recorded error without resetting it.
+fn world_last_error(handle: u64) -> Result<(i32, String), i32> {
+    let worlds = world_map()?;
+    match worlds.get(&handle) {
+        Some(world) => Ok(world.last_error.clone().unwrap_or((OK, String::new()))),
+        None => Err(set_error(INVALID_HANDLE, "unknown handle")),
     }
 }
 
+/// The status of the latest call that resolved this world (`OK` after a
+/// success). Unknown handles return `INVALID_HANDLE`.
 #[no_mangle]
-pub extern "C" fn pl_world_step(handle: u64, dt: f64, steps: u32) -> i32 {
-    if handle == 0 {
-        return set_error(INVALID_HANDLE, "invalid handle");
-    }
-    if let Err(code) = validate_dt(dt) {
-        return code;
+pub extern "C" fn pl_world_last_error_code(handle: u64) -> i32 {
+    match world_last_error(handle) {
+        Ok((code, _)) => code,
+        Err(code) => code,
     }
-    if let Err(code) = validate_steps(steps) {
-        return code;
-    }
-    let mut worlds = match world_map() {
-        Ok(m) => m,
-        Err(code) => return code,
-    };
-    let world = match worlds.get_mut(&handle) {
-        Some(w) => w,
-        None => return set_error(INVALID_HANDLE, "unknown handle"),
+}
+
+/// Like `pl_last_error_message` for the world's recorded error. Unknown
+/// handles return 0 and set the global error.
+#[no_mangle]
+pub extern "C" fn pl_world_last_error_message(handle: u64, out_buf: *mut u8, buf_len: u32) -> u32 {
+    let msg = match world_last_error(handle) {
+        Ok((_, msg)) => msg,
+        Err(_) => return 0,
     };
-    for _ in 0..steps {
-        world.vy -= G * dt;
-        world.y += world.vy * dt;
-        world.t += dt;
+    write_message(&msg, out_buf, buf_len)
+}
+
+#[no_mangle]
+pub extern "C" fn pl_set_default_gravity(g: f64) -> i32 {
+    if let Err(code) = validate_gravity(g) {
+        return code;
     }
+    DEFAULT_GRAVITY.store(g.to_bits(), Ordering::SeqCst);
     clear_error();
     OK
 }
 
 #[no_mangle]
-pub extern "C" fn pl_world_get_state(handle: u64, out_t: *mut f64, out_y: *mut f64, out_vy: *mut f64) -> i32 {
-    if handle == 0 {
-        return set_error(INVALID_HANDLE, "invalid handle");
-    }
-    if out_t.is_null() || out_y.is_null() || out_vy.is_null() {
+pub extern "C" fn pl_get_default_gravity() -> f64 {
+    f64::from_bits(DEFAULT_GRAVITY.load(Ordering::SeqCst))
+}
+
+/// Writes the configuration a fresh world starts from: at rest at the origin,
+/// the current default gravity, and no noise.
+#[no_mangle]
+pub extern "C" fn pl_get_defaults(out: *mut PlWorldConfig) -> i32 {
+    if out.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let defaults = PlWorldConfig {
+        y0: 0.0,
+        vy0: 0.0,
+        g: f64::from_bits(DEFAULT_GRAVITY.load(Ordering::SeqCst)),
+        noise_amplitude: 0.0,
+    };
+    unsafe {
+        *out = defaults;
+    }
+    clear_error();
+    OK
+}
+
+// Refuses to grow the registry to `total` worlds beyond the world limit.
+fn check_world_limit(total: usize) -> Result<(), i32> {
+    let limit = MAX_WORLDS.load(Ordering::SeqCst);
+    if limit != 0 && total > limit as usize {
+        return Err(set_error_detail(POLICY_DENIED, DETAIL_WORLD_LIMIT, "world limit reached"));
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub extern "C" fn pl_set_max_worlds(limit: u32) -> i32 {
+    MAX_WORLDS.store(limit, Ordering::SeqCst);
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_get_max_worlds() -> u32 {
+    MAX_WORLDS.load(Ordering::SeqCst)
+}
+
+/// Integration steps performed across all worlds since startup; monotonic
+/// apart from `pl_reset_for_testing`.
+#[no_mangle]
+pub extern "C" fn pl_total_steps_executed() -> u64 {
+    TOTAL_STEPS.load(Ordering::Relaxed)
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_create(y0: f64, vy0: f64) -> u64 {
+    if !y0.is_finite() || !vy0.is_finite() {
+        set_error(INVALID_ARGUMENT, "y0 and vy0 must be finite");
+        return 0;
+    }
+    let world = World::new(y0, vy0, f64::from_bits(DEFAULT_GRAVITY.load(Ordering::SeqCst)));
+    let mut worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if check_world_limit(worlds.len() + 1).is_err() {
+        return 0;
+    }
+    // Allocate under the registry lock so a handle is never observable
+    // before its world is inserted, and never hand out 0 (the error sentinel)
+    // or a handle that is still live should the counter ever wrap.
+    let handle = loop {
+        let candidate = HANDLE_COUNTER.fetch_add(1, Ordering::SeqCst);
+        if candidate != 0 && !worlds.contains_key(&candidate) {
+            break candidate;
+        }
+    };
+    worlds.insert(handle, world);
+    clear_error();
+    handle
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_destroy(handle: u64) {
+    if handle == 0 {
+        set_error(INVALID_HANDLE, "invalid handle");
+        return;
+    }
+    let mut worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+    if worlds.remove(&handle).is_some() {
+        clear_error();
+    } else {
+        set_error(INVALID_HANDLE, "unknown handle");
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_destroy_batch(handles: *const u64, count: u32, out_status: *mut i32) -> i32 {
+    if handles.is_null() && count > 0 {
+        return set_error(INVALID_ARGUMENT, "handles must be non-null");
+    }
+    let mut worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    let mut failed = 0u32;
+    for i in 0..count as usize {
+        let handle = unsafe { *handles.add(i) };
+        let status = if handle != 0 && worlds.remove(&handle).is_some() {
+            OK
+        } else {
+            failed += 1;
+            INVALID_HANDLE
+        };
+        if !out_status.is_null() {
+            unsafe {
+                *out_status.add(i) = status;
+            }
+        }
+    }
+    if failed > 0 {
+        return set_error(INVALID_HANDLE, format!("{failed} of {count} handles were invalid"));
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_count() -> u64 {
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    clear_error();
+    worlds.len() as u64
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_list(out_handles: *mut u64, cap: u32) -> u32 {
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if !out_handles.is_null() {
+        for (i, &handle) in worlds.keys().take(cap as usize).enumerate() {
+            unsafe {
+                *out_handles.add(i) = handle;
+            }
+        }
+    }
+    clear_error();
+    worlds.len() as u32
+}
+
+/// `pl_world_list` restricted to worlds matching `predicate`: landed (at
+/// rest on the floor bound), asleep (zero velocity and nothing unsupported
+/// accelerating it, e.g. settled on the floor), diverged, or moving upward.
+/// Returns the total match count.
+#[no_mangle]
+pub extern "C" fn pl_world_filter(predicate: i32, out_handles: *mut u64, cap: u32) -> u32 {
+    let matches: fn(&World) -> bool = match predicate {
+        FILTER_LANDED => landed,
+        FILTER_ASLEEP => |w| w.vy == 0.0 && supported_accel(w) == 0.0,
+        FILTER_DIVERGED => |w| w.diverged,
+        FILTER_RISING => |w| w.vy > 0.0,
+        _ => {
+            set_error(INVALID_ARGUMENT, "unknown predicate");
+            return 0;
+        }
+    };
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    let mut count = 0u32;
+    for (&handle, world) in worlds.iter() {
+        if !matches(world) {
+            continue;
+        }
+        if !out_handles.is_null() && count < cap {
+            unsafe {
+                *out_handles.add(count as usize) = handle;
+            }
+        }
+        count += 1;
+    }
+    clear_error();
+    count
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_cursor_begin() -> u64 {
+    let handles: Vec<u64> = match world_map() {
+        Ok(m) => m.keys().copied().collect(),
+        Err(_) => return 0,
+    };
+    let mut cursors = match CURSORS.lock() {
+        Ok(c) => c,
+        Err(_) => {
+            set_error(INTERNAL_ERROR, "failed to lock cursors");
+            return 0;
+        }
+    };
+    let id = CURSOR_COUNTER.fetch_add(1, Ordering::SeqCst);
+    cursors.insert(id, Cursor { handles, next: 0 });
+    clear_error();
+    id
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_cursor_next(cursor: u64, out_handle: *mut u64) -> i32 {
+    if out_handle.is_null() {
         return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
     }
+    let mut cursors = match CURSORS.lock() {
+        Ok(c) => c,
+        Err(_) => return set_error(INTERNAL_ERROR, "failed to lock cursors"),
+    };
+    let cursor = match cursors.get_mut(&cursor) {
+        Some(c) => c,
+        None => return set_error(INVALID_HANDLE, "unknown cursor"),
+    };
+    clear_error();
+    match cursor.handles.get(cursor.next) {
+        Some(&handle) => {
+            cursor.next += 1;
+            unsafe {
+                *out_handle = handle;
+            }
+            OK
+        }
+        None => CURSOR_END,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_cursor_end(cursor: u64) {
+    match CURSORS.lock() {
+        Ok(mut cursors) => {
+            if cursors.remove(&cursor).is_some() {
+                clear_error();
+            } else {
+                set_error(INVALID_HANDLE, "unknown cursor");
+            }
+        }
+        Err(_) => {
+            set_error(INTERNAL_ERROR, "failed to lock cursors");
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn pl_link_spring(handle_a: u64, handle_b: u64, k: f64, rest_length: f64) -> u64 {
+    if handle_a == 0 || handle_b == 0 {
+        set_error(INVALID_HANDLE, "invalid handle");
+        return 0;
+    }
+    if handle_a == handle_b {
+        set_error(INVALID_ARGUMENT, "a world cannot be linked to itself");
+        return 0;
+    }
+    if !k.is_finite() || k < 0.0 {
+        set_error(INVALID_ARGUMENT, "k must be finite and >= 0");
+        return 0;
+    }
+    if !rest_length.is_finite() || rest_length < 0.0 {
+        set_error(INVALID_ARGUMENT, "rest_length must be finite and >= 0");
+        return 0;
+    }
+    match world_map() {
+        Ok(worlds) if worlds.contains_key(&handle_a) && worlds.contains_key(&handle_b) => {}
+        Ok(_) => {
+            set_error(INVALID_HANDLE, "unknown handle");
+            return 0;
+        }
+        Err(_) => return 0,
+    }
+    let mut links = match LINKS.lock() {
+        Ok(l) => l,
+        Err(_) => {
+            set_error(INTERNAL_ERROR, "failed to lock links");
+            return 0;
+        }
+    };
+    let id = LINK_COUNTER.fetch_add(1, Ordering::SeqCst);
+    links.insert(
+        id,
+        SpringLink {
+            a: handle_a,
+            b: handle_b,
+            k,
+            rest_length,
+        },
+    );
+    clear_error();
+    id
+}
+
+#[no_mangle]
+pub extern "C" fn pl_unlink(link_id: u64) -> i32 {
+    let mut links = match LINKS.lock() {
+        Ok(l) => l,
+        Err(_) => return set_error(INTERNAL_ERROR, "failed to lock links"),
+    };
+    if links.remove(&link_id).is_none() {
+        return set_error(INVALID_HANDLE, "unknown link");
+    }
+    clear_error();
+    OK
+}
+
+/// Steps every world that has at least one spring link, adding each link's
+/// force `-k * (|ya - yb| - rest_length)` (pulling the pair together when
+/// stretched) evaluated at the start of the step. Links whose worlds have
+/// been destroyed are inert. Unlinked worlds are not stepped. Worlds at their
+/// time limit stop while the rest continue, and the call then reports
+/// `TIME_LIMIT_REACHED`. A rollback world whose step is undone likewise
+/// stops, and the call reports `NUMERICAL_OVERFLOW`. As with
+/// `pl_world_step`, every stepped world's error is reset (or set to the
+/// time limit or overflow) and its step timing updated.
+#[no_mangle]
+pub extern "C" fn pl_step_linked(dt: f64, steps: u32) -> i32 {
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let links: Vec<SpringLink> = match LINKS.lock() {
+        Ok(l) => l.values().copied().collect(),
+        Err(_) => return set_error(INTERNAL_ERROR, "failed to lock links"),
+    };
     let mut worlds = match world_map() {
         Ok(m) => m,
         Err(code) => return code,
     };
-    let world = match worlds.get(&handle) {
-        Some(w) => w,
-        None => return set_error(INVALID_HANDLE, "unknown handle"),
+    let live: Vec<SpringLink> = links
+        .into_iter()
+        .filter(|l| worlds.contains_key(&l.a) && worlds.contains_key(&l.b))
+        .collect();
+    // Integration time per stepped world, as `step_timed` measures it.
+    let mut nanos: BTreeMap<u64, u64> = BTreeMap::new();
+    for link in &live {
+        for handle in [link.a, link.b] {
+            worlds.get_mut(&handle).expect("linked world is live").last_error = None;
+            nanos.insert(handle, 0);
+        }
+    }
+    let mut accel: BTreeMap<u64, f64> = BTreeMap::new();
+    let mut limited = false;
+    let mut overflowed = false;
+    for _ in 0..steps {
+        accel.clear();
+        for link in &live {
+            let separation = worlds[&link.a].y - worlds[&link.b].y;
+            let force = -link.k * (separation.abs() - link.rest_length) * separation.signum();
+            *accel.entry(link.a).or_insert(0.0) += force;
+            *accel.entry(link.b).or_insert(0.0) -= force;
+        }
+        for (handle, &extra) in &accel {
+            let world = worlds.get_mut(handle).expect("linked world is live");
+            // Stopped for the rest of the call by a time limit or rollback.
+            if world.time_scale <= 0.0 || world.last_error.is_some() {
+                continue;
+            }
+            let start = Instant::now();
+            let h = dt * world.time_scale;
+            match checked_step(world, |world| advance_limited(world, h, extra)) {
+                Ok(true) => {}
+                Ok(false) => {
+                    world.last_error = Some((TIME_LIMIT_REACHED, TIME_LIMIT_MESSAGE.to_string()));
+                    limited = true;
+                }
+                Err(()) => {
+                    world.last_error = Some((NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE.to_string()));
+                    overflowed = true;
+                }
+            }
+            let elapsed = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            let total = nanos.get_mut(handle).expect("linked world is timed");
+            *total = total.saturating_add(elapsed);
+        }
+    }
+    for (handle, total) in nanos {
+        worlds.get_mut(&handle).expect("linked world is live").last_step_nanos = total;
+    }
+    if overflowed {
+        return set_error(NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE);
+    }
+    if limited {
+        return set_error(TIME_LIMIT_REACHED, TIME_LIMIT_MESSAGE);
+    }
+    clear_error();
+    OK
+}
+
+/// Total vertical momentum of the listed worlds, read under one lock: each
+/// primary particle counts with unit mass (as spring links treat it) plus
+/// `mass * vy` over its bodies. Unknown handles are skipped; a handle listed
+/// twice counts twice.
+#[no_mangle]
+pub extern "C" fn pl_total_momentum(handles: *const u64, count: u32, out_p: *mut f64) -> i32 {
+    if handles.is_null() && count > 0 {
+        return set_error(INVALID_ARGUMENT, "handles must be non-null");
+    }
+    if out_p.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointer must be non-null");
+    }
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    let mut total = 0.0;
+    for i in 0..count as usize {
+        let handle = unsafe { *handles.add(i) };
+        if let Some(world) = worlds.get(&handle) {
+            total += world.vy;
+            for body in &world.bodies {
+                total += body.mass * body.vy;
+            }
+        }
+    }
+    unsafe {
+        *out_p = total;
+    }
+    clear_error();
+    OK
+}
+
+/// Total mechanical energy of the listed worlds, read under one lock: each
+/// primary particle counts with unit mass, `0.5 * vy^2` plus the gravity
+/// potential `∫₀ʸ g ds`, and each body adds `mass` times the same terms.
+/// Regions, buoyancy and springs contribute nothing. Unknown handles are
+/// skipped; a handle listed twice counts twice.
+#[no_mangle]
+pub extern "C" fn pl_total_energy(handles: *const u64, count: u32, out_total: *mut f64) -> i32 {
+    if handles.is_null() && count > 0 {
+        return set_error(INVALID_ARGUMENT, "handles must be non-null");
+    }
+    if out_total.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointer must be non-null");
+    }
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    let mut total = 0.0;
+    for i in 0..count as usize {
+        let handle = unsafe { *handles.add(i) };
+        if let Some(world) = worlds.get(&handle) {
+            total += 0.5 * world.vy * world.vy + potential_at(world, world.y);
+            for body in &world.bodies {
+                total += body.mass * (0.5 * body.vy * body.vy + potential_at(world, body.y));
+            }
+        }
+    }
+    unsafe {
+        *out_total = total;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_handle_stats(out_min: *mut u64, out_max: *mut u64, out_next: *mut u64) -> i32 {
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    let min = worlds.keys().next().copied().unwrap_or(0);
+    let max = worlds.keys().next_back().copied().unwrap_or(0);
+    let next = HANDLE_COUNTER.load(Ordering::SeqCst);
+    drop(worlds);
+    for (out, value) in [(out_min, min), (out_max, max), (out_next, next)] {
+        if !out.is_null() {
+            unsafe {
+                *out = value;
+            }
+        }
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_gravity(handle: u64, g: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if let Err(code) = validate_gravity(g) {
+        return code;
+    }
+    if let Err(code) = with_world(handle, |world| world.g = g) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_schedule_gravity(handle: u64, at_t: f64, new_g: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !at_t.is_finite() {
+        return set_error(INVALID_ARGUMENT, "at_t must be finite");
+    }
+    if let Err(code) = validate_gravity(new_g) {
+        return code;
+    }
+    let accepted = match with_world(handle, |world| {
+        if at_t <= world.t {
+            return false;
+        }
+        let slot = world.gravity_schedule.partition_point(|&(t, _)| t <= at_t);
+        world.gravity_schedule.insert(slot, (at_t, new_g));
+        true
+    }) {
+        Ok(a) => a,
+        Err(code) => return code,
+    };
+    if !accepted {
+        return set_world_error(handle, INVALID_ARGUMENT, "at_t must be after the world's time");
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_get_gravity(handle: u64, out_g: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_g.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let g = match with_world(handle, |world| world.g) {
+        Ok(g) => g,
+        Err(code) => return code,
     };
     unsafe {
-        *out_t = world.t;
-        *out_y = world.y;
-        *out_vy = world.vy;
+        *out_g = g;
     }
     clear_error();
-    OK 
+    OK
+}
+
+/// `pl_world_set_gravity` with `value` in m/s^2, ft/s^2 or standard g,
+/// converted to m/s^2 before it is stored.
+#[no_mangle]
+pub extern "C" fn pl_world_set_gravity_units(handle: u64, unit: i32, value: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let factor = match unit {
+        GRAVITY_UNIT_MPS2 => 1.0,
+        GRAVITY_UNIT_FTPS2 => METERS_PER_FOOT,
+        GRAVITY_UNIT_STANDARD_G => STANDARD_GRAVITY,
+        _ => return set_error(INVALID_ARGUMENT, "unknown gravity unit"),
+    };
+    pl_world_set_gravity(handle, value * factor)
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_gravity_vector(handle: u64, gx: f64, gy: f64, gz: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !gx.is_finite() || !gy.is_finite() || !gz.is_finite() {
+        return set_error(INVALID_ARGUMENT, "gravity must be finite");
+    }
+    // Worlds are 1D along +y: only the vertical component acts, and `g` is
+    // stored as the magnitude pulling toward -y.
+    if let Err(code) = with_world(handle, |world| world.g = -gy) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_gravity_table(
+    handle: u64,
+    altitudes: *const f64,
+    gravities: *const f64,
+    count: u32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let table: Vec<(f64, f64)> = if count == 0 {
+        Vec::new()
+    } else {
+        if altitudes.is_null() || gravities.is_null() {
+            return set_error(INVALID_ARGUMENT, "table pointers must be non-null");
+        }
+        let altitudes = unsafe { std::slice::from_raw_parts(altitudes, count as usize) };
+        let gravities = unsafe { std::slice::from_raw_parts(gravities, count as usize) };
+        if altitudes.iter().chain(gravities).any(|v| !v.is_finite()) {
+            return set_error(INVALID_ARGUMENT, "table values must be finite");
+        }
+        if altitudes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return set_error(INVALID_ARGUMENT, "altitudes must be strictly increasing");
+        }
+        altitudes.iter().copied().zip(gravities.iter().copied()).collect()
+    };
+    if let Err(code) = with_world(handle, |world| world.gravity_table = table) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Appends a band whose `extra_accel` (along +y) acts on the particle while
+/// its `y` is in `[y_low, y_high)`. Overlapping bands add up.
+#[no_mangle]
+pub extern "C" fn pl_world_add_region(handle: u64, y_low: f64, y_high: f64, extra_accel: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !y_low.is_finite() || !y_high.is_finite() || !extra_accel.is_finite() {
+        return set_error(INVALID_ARGUMENT, "region values must be finite");
+    }
+    if y_low >= y_high {
+        return set_error(INVALID_ARGUMENT, "y_low must be < y_high");
+    }
+    if let Err(code) = with_world(handle, |world| world.regions.push((y_low, y_high, extra_accel))) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_clear_regions(handle: u64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if let Err(code) = with_world(handle, |world| world.regions.clear()) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Immerses the primary particle in a fluid column: while `y <
+/// fluid_top_y` each step adds the upward buoyant acceleration
+/// `fluid_density * object_volume * g(y)` (the particle has unit mass), and
+/// above the surface nothing. Bodies are unaffected.
+#[no_mangle]
+pub extern "C" fn pl_world_set_buoyancy(
+    handle: u64,
+    fluid_density: f64,
+    object_volume: f64,
+    fluid_top_y: f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+    if !positive(fluid_density) || !positive(object_volume) {
+        return set_error(INVALID_ARGUMENT, "fluid_density and object_volume must be finite and > 0");
+    }
+    if !fluid_top_y.is_finite() {
+        return set_error(INVALID_ARGUMENT, "fluid_top_y must be finite");
+    }
+    let buoyancy = Buoyancy {
+        fluid_density,
+        object_volume,
+        fluid_top_y,
+    };
+    if let Err(code) = with_world(handle, |world| world.buoyancy = Some(buoyancy)) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_bounds(handle: u64, floor_y: f64, ceiling_y: f64, restitution: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !floor_y.is_finite() || !ceiling_y.is_finite() {
+        return set_error(INVALID_ARGUMENT, "bounds must be finite");
+    }
+    if floor_y >= ceiling_y {
+        return set_error(INVALID_ARGUMENT, "floor_y must be < ceiling_y");
+    }
+    if !(0.0..=1.0).contains(&restitution) {
+        return set_error(INVALID_ARGUMENT, "restitution must be in [0, 1]");
+    }
+    let bounds = Bounds {
+        floor_y,
+        ceiling_y,
+        restitution,
+    };
+    // A body already outside the new channel is placed at rest on the nearest
+    // bound rather than bounced in on the first step; landing it on the floor
+    // counts as a contact.
+    if let Err(code) = with_world(handle, |world| {
+        if world.y < floor_y {
+            world.y = floor_y;
+            world.vy = 0.0;
+            world.last_contact_t = world.t;
+        } else if world.y > ceiling_y {
+            world.y = ceiling_y;
+            world.vy = 0.0;
+        }
+        world.bounds = Some(bounds);
+    }) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Reads back the ground: the floor bound and its restitution with
+/// `enabled` 1, or `y = 0` (the event ground), restitution 0 and `enabled` 0
+/// when no bounds are set. Null outputs are skipped.
+#[no_mangle]
+pub extern "C" fn pl_world_get_ground(
+    handle: u64,
+    out_ground_y: *mut f64,
+    out_restitution: *mut f64,
+    out_enabled: *mut i32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_ground_y.is_null() && out_restitution.is_null() && out_enabled.is_null() {
+        return set_error(INVALID_ARGUMENT, "at least one output pointer must be non-null");
+    }
+    let bounds = match with_world(handle, |world| world.bounds) {
+        Ok(b) => b,
+        Err(code) => return code,
+    };
+    let (ground_y, restitution) = bounds.map_or((0.0, 0.0), |b| (b.floor_y, b.restitution));
+    unsafe {
+        if !out_ground_y.is_null() {
+            *out_ground_y = ground_y;
+        }
+        if !out_restitution.is_null() {
+            *out_restitution = restitution;
+        }
+        if !out_enabled.is_null() {
+            *out_enabled = i32::from(bounds.is_some());
+        }
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_boundary_mode(handle: u64, mode: i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let Some(mode) = BoundaryMode::from_code(mode) else {
+        return set_error(INVALID_ARGUMENT, "mode must be 0 (reflect), 1 (clamp) or 2 (wrap)");
+    };
+    if let Err(code) = with_world(handle, |world| world.boundary_mode = mode) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_contact_epsilon(handle: u64, eps: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !eps.is_finite() || eps < 0.0 {
+        return set_error(INVALID_ARGUMENT, "eps must be finite and >= 0");
+    }
+    if let Err(code) = with_world(handle, |world| world.contact_epsilon = eps) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_time_limit(handle: u64, t_max: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if t_max.is_nan() {
+        return set_error(INVALID_ARGUMENT, "t_max must not be NaN");
+    }
+    let accepted = match with_world(handle, |world| {
+        if t_max <= world.t {
+            return false;
+        }
+        world.time_limit = t_max;
+        true
+    }) {
+        Ok(a) => a,
+        Err(code) => return code,
+    };
+    if !accepted {
+        return set_world_error(handle, INVALID_ARGUMENT, "t_max must be greater than the world's time");
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_time_scale(handle: u64, scale: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !scale.is_finite() || scale < 0.0 {
+        return set_error(INVALID_ARGUMENT, "scale must be finite and >= 0");
+    }
+    if let Err(code) = with_world(handle, |world| world.time_scale = scale) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Shifts the `t` this world reports by `offset` so worlds created at
+/// different times can share one timeline. Its own clock is unchanged.
+#[no_mangle]
+pub extern "C" fn pl_world_set_time_offset(handle: u64, offset: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !offset.is_finite() {
+        return set_error(INVALID_ARGUMENT, "offset must be finite");
+    }
+    if let Err(code) = with_world(handle, |world| world.time_offset = offset) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_restitution_jitter(handle: u64, jitter: f64, seed: u64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !(0.0..=1.0).contains(&jitter) {
+        return set_error(INVALID_ARGUMENT, "jitter must be in [0, 1]");
+    }
+    if let Err(code) = with_world(handle, |world| {
+        world.restitution_jitter = jitter;
+        world.jitter_rng_state = seed;
+    }) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_flush_denormals(handle: u64, enable: i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if let Err(code) = with_world(handle, |world| world.flush_denormals = enable != 0) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Enables or disables step rollback: when on, a step that leaves the state
+/// non-finite is undone and the stepping call stops with
+/// `NUMERICAL_OVERFLOW`. Every call that steps the world honors it, and
+/// probes stop their copy the same way. Costs a world copy per step.
+#[no_mangle]
+pub extern "C" fn pl_world_set_rollback(handle: u64, enable: i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if let Err(code) = with_world(handle, |world| world.rollback = enable != 0) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_noise(handle: u64, seed: u64, amplitude: f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !amplitude.is_finite() || amplitude < 0.0 {
+        return set_error(INVALID_ARGUMENT, "amplitude must be finite and >= 0");
+    }
+    if let Err(code) = with_world(handle, |world| {
+        world.noise_amplitude = amplitude;
+        world.rng_state = seed;
+    }) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+/// Reseeds the force noise of every live world with noise enabled: the
+/// world of rank `i` in handle order (counting all live worlds) gets the
+/// `i`-th SplitMix64 output seeded with `master_seed`. Seeds depend on the
+/// creation order, not the handle values, so a rebuilt registry replays
+/// identically. Worlds without noise are left alone.
+#[no_mangle]
+pub extern "C" fn pl_seed_all(master_seed: u64) -> i32 {
+    let mut worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    let mut state = master_seed;
+    for world in worlds.values_mut() {
+        let seed = next_u64(&mut state);
+        if world.noise_amplitude > 0.0 {
+            world.rng_state = seed;
+        }
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_add_body(handle: u64, y: f64, vy: f64, mass: f64, radius: f64) -> u64 {
+    if handle == 0 {
+        set_error(INVALID_HANDLE, "invalid handle");
+        return 0;
+    }
+    if !y.is_finite() || !vy.is_finite() {
+        set_error(INVALID_ARGUMENT, "y and vy must be finite");
+        return 0;
+    }
+    if !mass.is_finite() || mass <= 0.0 {
+        set_error(INVALID_ARGUMENT, "mass must be finite and > 0");
+        return 0;
+    }
+    if !radius.is_finite() || radius < 0.0 {
+        set_error(INVALID_ARGUMENT, "radius must be finite and >= 0");
+        return 0;
+    }
+    let body = bodies::Body { y, vy, mass, radius };
+    // Body ids are 1-based so that 0 can signal failure, as with handles.
+    match with_world(handle, |world| {
+        world.bodies.push(body);
+        world.bodies.len() as u64
+    }) {
+        Ok(id) => {
+            clear_error();
+            id
+        }
+        Err(_) => 0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_get_body_state(handle: u64, body: u64, out_y: *mut f64, out_vy: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_y.is_null() || out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let found = match with_world(handle, |world| {
+        let index = usize::try_from(body).ok()?.checked_sub(1)?;
+        world.bodies.get(index).map(|b| (b.y, b.vy))
+    }) {
+        Ok(found) => found,
+        Err(code) => return code,
+    };
+    let (y, vy) = match found {
+        Some(state) => state,
+        None => return set_error(INVALID_ARGUMENT, "unknown body"),
+    };
+    unsafe {
+        *out_y = y;
+        *out_vy = vy;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_step(handle: u64, dt: f64, steps: u32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    match with_world(handle, |world| step_live(world, dt, steps)) {
+        Ok(Ok(true)) => return time_limit_error(handle),
+        Ok(Ok(false)) => {}
+        Ok(Err(())) => return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+        Err(code) => return code,
+    }
+    clear_error();
+    OK
+}
+
+/// Audit aid: one step of exactly `dt` (no time scale), writing `vy` right
+/// after the gravity kick and `y` right after the drift, before contacts,
+/// bodies or flushing adjust them. Refused if it would pass the time limit.
+/// Exported by debug builds only.
+#[cfg(debug_assertions)]
+#[no_mangle]
+pub extern "C" fn pl_world_step_trace(handle: u64, dt: f64, out_y: *mut f64, out_vy: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_y.is_null() || out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    let trace = match with_world(handle, |world| {
+        if world.t + dt > world.time_limit {
+            return None;
+        }
+        Some(timed(world, |world| checked_step(world, |world| advance_with_accel(world, dt, 0.0))))
+    }) {
+        Ok(trace) => trace,
+        Err(code) => return code,
+    };
+    let (vy, y) = match trace {
+        Some(Ok(trace)) => trace,
+        Some(Err(())) => return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+        None => return time_limit_error(handle),
+    };
+    unsafe {
+        *out_y = y;
+        *out_vy = vy;
+    }
+    clear_error();
+    OK
+}
+
+/// `pl_world_step` without validating `dt` or `steps` (including the step
+/// cap). Only the handle is checked. A non-finite or non-positive `dt`
+/// silently corrupts the world's state (typically to NaN).
+#[no_mangle]
+pub extern "C" fn pl_world_step_unchecked(handle: u64, dt: f64, steps: u32) -> i32 {
+    match with_world(handle, |world| step_live(world, dt, steps)) {
+        Ok(Ok(true)) => time_limit_error(handle),
+        Ok(Ok(false)) => {
+            clear_error();
+            OK
+        }
+        Ok(Err(())) => set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+        Err(code) => code,
+    }
+}
+
+/// Steps with a host-supplied acceleration in place of gravity and force
+/// noise; bounds, bodies and the world's time scale and limit still apply.
+/// The callback runs with the registry lock held, so it must not call back
+/// into the kernel. A non-finite acceleration stops stepping before it is
+/// applied and reports `INVALID_ARGUMENT`; earlier steps are kept.
+#[no_mangle]
+pub extern "C" fn pl_world_step_custom(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    accel_cb: Option<AccelCallback>,
+    user: *mut c_void,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let Some(accel_cb) = accel_cb else {
+        return set_error(INVALID_ARGUMENT, "accel_cb must be non-null");
+    };
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let outcome = with_world(handle, |world| {
+        timed(world, |world| {
+            if world.time_scale == 0.0 {
+                return Ok(false);
+            }
+            let dt = dt * world.time_scale;
+            for _ in 0..steps {
+                let h = dt.min(world.time_limit - world.t);
+                if h <= 0.0 {
+                    return Ok(true);
+                }
+                let accel = accel_cb(world.t, world.y, world.vy, user);
+                if !accel.is_finite() {
+                    return Err(INVALID_ARGUMENT);
+                }
+                if checked_step(world, |world| integrate(world, h, accel)).is_err() {
+                    return Err(NUMERICAL_OVERFLOW);
+                }
+                if h < dt {
+                    world.t = world.time_limit;
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+    });
+    match outcome {
+        Ok(Ok(true)) => time_limit_error(handle),
+        Ok(Ok(false)) => {
+            clear_error();
+            OK
+        }
+        Ok(Err(INVALID_ARGUMENT)) => set_world_error(
+            handle,
+            INVALID_ARGUMENT,
+            "acceleration callback returned a non-finite value",
+        ),
+        Ok(Err(_)) => set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+        Err(code) => code,
+    }
+}
+
+/// Steps like `pl_world_step` until `cond_cb`, called with the state after
+/// each step, returns nonzero, writing the steps taken. Reaching `max_steps`
+/// with the condition unmet is not an error. The callback runs with the
+/// registry lock held, so it must not call back into the kernel.
+#[no_mangle]
+pub extern "C" fn pl_world_step_until(
+    handle: u64,
+    dt: f64,
+    max_steps: u32,
+    cond_cb: Option<CondCallback>,
+    user: *mut c_void,
+    out_steps: *mut u32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let Some(cond_cb) = cond_cb else {
+        return set_error(INVALID_ARGUMENT, "cond_cb must be non-null");
+    };
+    if out_steps.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(max_steps) {
+        return code;
+    }
+    let (steps, limited, overflowed) = match with_world(handle, |world| {
+        timed(world, |world| {
+            let mut steps = 0;
+            let mut limited = false;
+            if world.time_scale > 0.0 {
+                let dt = dt * world.time_scale;
+                while steps < max_steps {
+                    if world.t >= world.time_limit {
+                        limited = true;
+                        break;
+                    }
+                    let Ok(advanced) = checked_step(world, |world| advance_limited(world, dt, 0.0)) else {
+                        return (steps, false, true);
+                    };
+                    steps += 1;
+                    limited = !advanced;
+                    if limited || cond_cb(world.t, world.y, world.vy, user) != 0 {
+                        break;
+                    }
+                }
+            }
+            (steps, limited, false)
+        })
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_steps = steps;
+    }
+    if overflowed {
+        return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE);
+    }
+    if limited {
+        return time_limit_error(handle);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_step_with_error(handle: u64, dt: f64, steps: u32, out_error: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_error.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let (max_error, limited, overflowed) = match with_world(handle, |world| {
+        timed(world, |world| {
+            let mut max_error = 0.0_f64;
+            if world.time_scale == 0.0 {
+                return (max_error, false, false);
+            }
+            let dt = dt * world.time_scale;
+            for _ in 0..steps {
+                // The last step before the time limit is shortened to reach it.
+                let h = dt.min(world.time_limit - world.t);
+                if h <= 0.0 {
+                    return (max_error, true, false);
+                }
+                let Ok(err) = checked_step(world, |world| {
+                    let mut fine = world.clone();
+                    advance(&mut fine, h * 0.5);
+                    advance(&mut fine, h * 0.5);
+                    advance(world, h);
+                    (world.y - fine.y).abs().max((world.vy - fine.vy).abs())
+                }) else {
+                    return (max_error, false, true);
+                };
+                max_error = max_error.max(err);
+                if h < dt {
+                    world.t = world.time_limit;
+                    return (max_error, true, false);
+                }
+            }
+            (max_error, false, false)
+        })
+    }) {
+        Ok(e) => e,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_error = max_error;
+    }
+    if overflowed {
+        return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE);
+    }
+    if limited {
+        return time_limit_error(handle);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_get_state(handle: u64, out_t: *mut f64, out_y: *mut f64, out_vy: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_t.is_null() && out_y.is_null() && out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "at least one output pointer must be non-null");
+    }
+    let state = match with_world(handle, measured_state) {
+        Ok(state) => state,
+        Err(code) => return code,
+    };
+    write_state_partial(&state, out_t, out_y, out_vy);
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_get_true_state(
+    handle: u64,
+    out_t: *mut f64,
+    out_y: *mut f64,
+    out_vy: *mut f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_t.is_null() && out_y.is_null() && out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "at least one output pointer must be non-null");
+    }
+    let state = match with_world(handle, |world| reported_state(world)) {
+        Ok(state) => state,
+        Err(code) => return code,
+    };
+    write_state_partial(&state, out_t, out_y, out_vy);
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_set_measurement_noise(handle: u64, y_sigma: f64, vy_sigma: f64, seed: u64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if !(y_sigma.is_finite() && y_sigma >= 0.0 && vy_sigma.is_finite() && vy_sigma >= 0.0) {
+        return set_error(INVALID_ARGUMENT, "sigmas must be finite and >= 0");
+    }
+    if let Err(code) = with_world(handle, |world| {
+        world.measurement = Measurement {
+            y_sigma,
+            vy_sigma,
+            rng_state: seed,
+        };
+    }) {
+        return code;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_equals(handle_a: u64, handle_b: u64, tol: f64, out_equal: *mut i32) -> i32 {
+    if handle_a == 0 || handle_b == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_equal.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if !tol.is_finite() || tol < 0.0 {
+        return set_error(INVALID_ARGUMENT, "tol must be finite and >= 0");
+    }
+    let equal = {
+        let worlds = match world_map() {
+            Ok(m) => m,
+            Err(code) => return code,
+        };
+        match (worlds.get(&handle_a), worlds.get(&handle_b)) {
+            (Some(a), Some(b)) => a.approx_eq(b, tol),
+            _ => return set_error(INVALID_HANDLE, "unknown handle"),
+        }
+    };
+    unsafe {
+        *out_equal = i32::from(equal);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_step_count(handle: u64, out_count: *mut u64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_count.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let count = match with_world(handle, |world| world.step_count) {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_count = count;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_net_acceleration(handle: u64, out_a: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_a.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    // The deterministic part of `advance`'s acceleration; force noise is a
+    // random draw and is left out so the query does not consume the stream.
+    let accel = match with_world(handle, |world| deterministic_accel(world)) {
+        Ok(a) => a,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_a = accel;
+    }
+    clear_error();
+    OK
+}
+
+/// Writes the largest deterministic force term on the primary particle at
+/// its current state (unit mass, so force equals acceleration): gravity,
+/// the summed force regions containing `y`, or buoyancy, with its absolute
+/// magnitude. Ties go to the lower kind code, so a world with no force at
+/// all reports gravity with magnitude 0. Noise, springs and drag are not
+/// candidates.
+#[no_mangle]
+pub extern "C" fn pl_world_dominant_force(handle: u64, out_kind: *mut i32, out_magnitude: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_kind.is_null() || out_magnitude.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let dominant = match with_world(handle, |world| {
+        let terms = [
+            (FORCE_GRAVITY, gravity_at(world, world.y)),
+            (FORCE_REGIONS, region_accel(world, world.y)),
+            (FORCE_BUOYANCY, buoyancy_accel(world, world.y)),
+        ];
+        terms
+            .into_iter()
+            .map(|(kind, accel)| (kind, accel.abs()))
+            .fold((FORCE_GRAVITY, 0.0), |best, term| if term.1 > best.1 { term } else { best })
+    }) {
+        Ok(d) => d,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_kind = dominant.0;
+        *out_magnitude = dominant.1;
+    }
+    clear_error();
+    OK
+}
+
+/// Drag force on the primary particle at its current state. The kernel has
+/// no drag model, so a live world always reports 0; the handle and pointer
+/// are still validated, so hosts can wire it up before drag exists.
+#[no_mangle]
+pub extern "C" fn pl_world_drag_force(handle: u64, out_f: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_f.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = with_world(handle, |_| ()) {
+        return code;
+    }
+    unsafe {
+        *out_f = 0.0;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_integrator_order(handle: u64, out_order: *mut u32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_order.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    // Every world uses semi-implicit Euler, a first-order method.
+    if let Err(code) = with_world(handle, |_| ()) {
+        return code;
+    }
+    unsafe {
+        *out_order = 1;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_is_linear(handle: u64, out: *mut i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let linear = match with_world(handle, |world| world.is_linear()) {
+        Ok(l) => l,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out = i32::from(linear);
+    }
+    clear_error();
+    OK
+}
+
+/// Reconstructs where a ballistic world came from: the state at the most
+/// recent apex or ground crossing, found analytically. The launch time is
+/// reported on the offset timeline, like `t` in the state getters.
+/// `POLICY_DENIED` for nonlinear worlds or when neither lies in the past.
+#[no_mangle]
+pub extern "C" fn pl_world_origin(
+    handle: u64,
+    out_launch_y: *mut f64,
+    out_launch_vy: *mut f64,
+    out_launch_t: *mut f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_launch_y.is_null() || out_launch_vy.is_null() || out_launch_t.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let origin = match with_world(handle, |world| {
+        if world.is_linear() {
+            events::ballistic_origin(world)
+                .map(|(y, vy, t)| (y, vy, t + world.time_offset))
+                .ok_or("no apex or ground crossing in the past")
+        } else {
+            Err("world is not ballistic")
+        }
+    }) {
+        Ok(o) => o,
+        Err(code) => return code,
+    };
+    let (y, vy, t) = match origin {
+        Ok(o) => o,
+        Err(msg) => return set_world_error(handle, POLICY_DENIED, msg),
+    };
+    unsafe {
+        *out_launch_y = y;
+        *out_launch_vy = vy;
+        *out_launch_t = t;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_check_stability(handle: u64, dt: f64, out_stable: *mut i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_stable.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    // Semi-implicit Euler on an oscillator of angular frequency omega is
+    // stable for omega * dt < 2.
+    let stable = match with_world(handle, |world| {
+        let omega = max_stiffness(world).sqrt();
+        omega * dt * world.time_scale < 2.0
+    }) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_stable = i32::from(stable);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_max_speed(handle: u64, out_v: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_v.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let speed = match with_world(handle, |world| world.max_speed) {
+        Ok(v) => v,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_v = speed;
+    }
+    clear_error();
+    OK
+}
+
+/// Writes the simulated time since the primary particle last touched the
+/// floor, or the world's total simulated time if it never has. Wrapping
+/// through the floor is not a contact.
+#[no_mangle]
+pub extern "C" fn pl_world_airborne_time(handle: u64, out_t: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_t.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let airborne = match with_world(handle, |world| world.t - world.last_contact_t) {
+        Ok(t) => t,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_t = airborne;
+    }
+    clear_error();
+    OK
+}
+
+/// Writes the kinetic energy the floor has removed from the primary particle
+/// since creation, with unit mass: `0.5 * (v_before^2 - v_after^2)` summed
+/// over bounces with restitution below 1, plus the full `0.5 * v^2` of
+/// clamped and settled contacts. Ceiling contacts are not counted. There is
+/// no reset; the total lives as long as the world and is archived with it.
+#[no_mangle]
+pub extern "C" fn pl_world_energy_dissipated(handle: u64, out_joules: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_joules.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let dissipated = match with_world(handle, |world| world.energy_dissipated) {
+        Ok(e) => e,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_joules = dissipated;
+    }
+    clear_error();
+    OK
+}
+
+/// Writes 1 if any step since creation left the particle's state non-finite,
+/// else 0. The flag is sticky: later successful calls do not clear it.
+#[no_mangle]
+pub extern "C" fn pl_world_has_diverged(handle: u64, out: *mut i32) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let diverged = match with_world(handle, |world| world.diverged) {
+        Ok(d) => d,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out = i32::from(diverged);
+    }
+    clear_error();
+    OK
+}
+
+/// Writes the wall-clock seconds since the world was created (restored
+/// worlds count from their original creation), never negative.
+#[no_mangle]
+pub extern "C" fn pl_world_age_seconds(handle: u64, out_age: *mut f64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_age.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let created = match with_world(handle, |world| world.created_unix_nanos) {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_age = unix_nanos_now().saturating_sub(created) as f64 / 1e9;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_last_step_nanos(handle: u64, out_ns: *mut u64) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_ns.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let nanos = match with_world(handle, |world| world.last_step_nanos) {
+        Ok(n) => n,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_ns = nanos;
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_get_state_struct(handle: u64, out: *mut PlState) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    let state = match with_world(handle, measured_state) {
+        Ok(state) => state,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out = state;
+    }
+    clear_error();
+    OK
+}
+
+/// Writes the first `buf_len` bytes of the encoded state, like
+/// `pl_world_list` truncates, and returns the full encoded length.
+#[no_mangle]
+pub extern "C" fn pl_world_get_state_proto(handle: u64, out_buf: *mut u8, buf_len: u32) -> u32 {
+    if handle == 0 {
+        set_error(INVALID_HANDLE, "invalid handle");
+        return 0;
+    }
+    let bytes = match with_world(handle, |world| proto::encode_state(world)) {
+        Ok(b) => b,
+        Err(_) => return 0,
+    };
+    if !out_buf.is_null() {
+        let copy_len = std::cmp::min(bytes.len(), buf_len as usize);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buf, copy_len);
+        }
+    }
+    clear_error();
+    bytes.len() as u32
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_step_get(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    out_t: *mut f64,
+    out_y: *mut f64,
+    out_vy: *mut f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_t.is_null() || out_y.is_null() || out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let (state, outcome) = match with_world(handle, |world| {
+        let outcome = step_live(world, dt, steps);
+        (measured_state(world), outcome)
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_t = state.t;
+        *out_y = state.y;
+        *out_vy = state.vy;
+    }
+    match outcome {
+        Ok(true) => return time_limit_error(handle),
+        Ok(false) => {}
+        Err(()) => return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_probe(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    out_t: *mut f64,
+    out_y: *mut f64,
+    out_vy: *mut f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_t.is_null() || out_y.is_null() || out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    // Step a private copy so the registry world, including its random
+    // streams, is left exactly as it was.
+    let (state, outcome) = match with_world(handle, |world| {
+        let mut probe = world.clone();
+        let outcome = step_live(&mut probe, dt, steps);
+        (measured_state(&mut probe), outcome)
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_t = state.t;
+        *out_y = state.y;
+        *out_vy = state.vy;
+    }
+    match outcome {
+        Ok(true) => return time_limit_error(handle),
+        Ok(false) => {}
+        Err(()) => return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+    }
+    clear_error();
+    OK
+}
+
+// Writes a `t,y,vy` header and one row per step of `world`, stopping after
+// the row that reaches the time limit. Returns whether the limit was hit.
+fn write_trajectory(path: &str, world: &mut World, dt: f64, steps: u32) -> std::io::Result<bool> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "t,y,vy")?;
+    let mut limited = false;
+    for _ in 0..steps {
+        let t_before = world.t;
+        limited = step_timed(world, dt, 1);
+        if world.t == t_before && limited {
+            break;
+        }
+        let state = measured_state(world);
+        writeln!(out, "{},{},{}", state.t, state.y, state.vy)?;
+        if limited {
+            break;
+        }
+    }
+    out.flush()?;
+    Ok(limited)
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_trajectory_to_file(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    path: *const u8,
+    path_len: u32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if path.is_null() {
+        return set_error(INVALID_ARGUMENT, "path must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(path, path_len as usize) };
+    let path = match std::str::from_utf8(bytes) {
+        Ok(p) if !p.is_empty() => p,
+        Ok(_) => return set_error(INVALID_ARGUMENT, "path must be non-empty"),
+        Err(_) => return set_error(INVALID_ARGUMENT, "path must be valid UTF-8"),
+    };
+    // Copy under the lock, then do the file IO without holding it.
+    let mut copy = match with_world(handle, |world| world.clone()) {
+        Ok(w) => w,
+        Err(code) => return code,
+    };
+    match write_trajectory(path, &mut copy, dt, steps) {
+        Ok(true) => return time_limit_error(handle),
+        Ok(false) => {}
+        Err(err) => return set_world_error(handle, IO_ERROR, format!("failed to write trajectory: {err}")),
+    }
+    clear_error();
+    OK
+}
+
+/// Steps a copy of the world like `pl_world_trajectory_to_file` and hands
+/// the rows to `cb` in chunks of `batch` (the last may be shorter). The
+/// callback runs without any kernel lock held and may call back into the
+/// kernel; the chunk pointers are valid only during the call.
+#[no_mangle]
+pub extern "C" fn pl_world_stream(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    batch: u32,
+    cb: Option<StreamCallback>,
+    user: *mut c_void,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    let Some(cb) = cb else {
+        return set_error(INVALID_ARGUMENT, "cb must be non-null");
+    };
+    if batch == 0 {
+        return set_error(INVALID_ARGUMENT, "batch must be > 0");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    let mut copy = match with_world(handle, |world| world.clone()) {
+        Ok(w) => w,
+        Err(code) => return code,
+    };
+    let cap = batch.min(steps) as usize;
+    let mut ts = Vec::with_capacity(cap);
+    let mut ys = Vec::with_capacity(cap);
+    let mut vys = Vec::with_capacity(cap);
+    let deliver = |ts: &mut Vec<f64>, ys: &mut Vec<f64>, vys: &mut Vec<f64>| {
+        if !ts.is_empty() {
+            cb(ts.as_ptr(), ys.as_ptr(), vys.as_ptr(), ts.len() as u32, user);
+            ts.clear();
+            ys.clear();
+            vys.clear();
+        }
+    };
+    let mut limited = false;
+    for _ in 0..steps {
+        let t_before = copy.t;
+        limited = step_timed(&mut copy, dt, 1);
+        if copy.t == t_before && limited {
+            break;
+        }
+        let state = measured_state(&mut copy);
+        ts.push(state.t);
+        ys.push(state.y);
+        vys.push(state.vy);
+        if ts.len() == cap {
+            deliver(&mut ts, &mut ys, &mut vys);
+        }
+        if limited {
+            break;
+        }
+    }
+    deliver(&mut ts, &mut ys, &mut vys);
+    if limited {
+        return time_limit_error(handle);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_trajectory_stats(
+    handle: u64,
+    dt: f64,
+    steps: u32,
+    out_min_y: *mut f64,
+    out_max_y: *mut f64,
+    out_mean_y: *mut f64,
+    out_final_vy: *mut f64,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_min_y.is_null() || out_max_y.is_null() || out_mean_y.is_null() || out_final_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    // Aggregates the rows `pl_world_trajectory_to_file` would write, on a
+    // copy of the world.
+    let (min_y, max_y, mean_y, final_vy, limited) = match with_world(handle, |world| {
+        let mut copy = world.clone();
+        let (mut min_y, mut max_y, mut sum_y, mut rows) = (f64::INFINITY, f64::NEG_INFINITY, 0.0, 0u32);
+        let mut final_vy = 0.0;
+        let mut limited = false;
+        for _ in 0..steps {
+            let t_before = copy.t;
+            limited = step_timed(&mut copy, dt, 1);
+            if copy.t == t_before && limited {
+                break;
+            }
+            let state = measured_state(&mut copy);
+            min_y = min_y.min(state.y);
+            max_y = max_y.max(state.y);
+            sum_y += state.y;
+            rows += 1;
+            final_vy = state.vy;
+            if limited {
+                break;
+            }
+        }
+        if rows == 0 {
+            // Already at the time limit: the trajectory is the current state.
+            let state = measured_state(&mut copy);
+            return (state.y, state.y, state.y, state.vy, limited);
+        }
+        (min_y, max_y, sum_y / f64::from(rows), final_vy, limited)
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_min_y = min_y;
+        *out_max_y = max_y;
+        *out_mean_y = mean_y;
+        *out_final_vy = final_vy;
+    }
+    if limited {
+        return time_limit_error(handle);
+    }
+    clear_error();
+    OK
+}
+
+/// Steps by `dt` until the world is at rest (see `at_rest`), writing the
+/// steps taken. Gives up with `POLICY_DENIED` / `STEP_LIMIT` after
+/// `MAX_STEPS` steps, or at once if a zero time scale leaves it moving.
+#[no_mangle]
+pub extern "C" fn pl_world_step_to_rest(
+    handle: u64,
+    dt: f64,
+    v_eps: f64,
+    a_eps: f64,
+    out_steps: *mut u32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_steps.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if !(v_eps.is_finite() && v_eps > 0.0 && a_eps.is_finite() && a_eps > 0.0) {
+        return set_error(INVALID_ARGUMENT, "v_eps and a_eps must be positive");
+    }
+    let (steps, status) = match with_world(handle, |world| {
+        timed(world, |world| {
+            let dt = dt * world.time_scale;
+            let mut steps = 0;
+            let mut status = OK;
+            while !at_rest(world, v_eps, a_eps) {
+                if steps == MAX_STEPS || world.time_scale == 0.0 {
+                    status = POLICY_DENIED;
+                    break;
+                }
+                if world.t >= world.time_limit {
+                    status = TIME_LIMIT_REACHED;
+                    break;
+                }
+                let Ok(advanced) = checked_step(world, |world| advance_limited(world, dt, 0.0)) else {
+                    status = NUMERICAL_OVERFLOW;
+                    break;
+                };
+                steps += 1;
+                if !advanced {
+                    status = TIME_LIMIT_REACHED;
+                    break;
+                }
+            }
+            (steps, status)
+        })
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_steps = steps;
+    }
+    match status {
+        POLICY_DENIED => set_world_error_detail(
+            handle,
+            POLICY_DENIED,
+            DETAIL_STEP_LIMIT,
+            "world did not come to rest within the step limit",
+        ),
+        TIME_LIMIT_REACHED => time_limit_error(handle),
+        NUMERICAL_OVERFLOW => set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE),
+        _ => {
+            clear_error();
+            OK
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_step_to_event(
+    handle: u64,
+    dt_max: f64,
+    out_event_t: *mut f64,
+    out_kind: *mut i32,
+) -> i32 {
+    if handle == 0 {
+        return set_error(INVALID_HANDLE, "invalid handle");
+    }
+    if out_event_t.is_null() || out_kind.is_null() {
+        return set_error(INVALID_ARGUMENT, "output pointers must be non-null");
+    }
+    if let Err(code) = validate_dt(dt_max) {
+        return code;
+    }
+    let (t, kind, limited, overflowed) = match with_world(handle, |world| {
+        timed(world, |world| {
+            let mut kind = events::EVENT_TIMEOUT;
+            let mut limited = false;
+            if world.time_scale > 0.0 {
+                let window = dt_max * world.time_scale;
+                let left = world.time_limit - world.t;
+                if left <= 0.0 {
+                    limited = true;
+                } else {
+                    // The whole search is one step for rollback.
+                    match checked_step(world, |world| events::step_to_event(world, window.min(left))) {
+                        Ok(found) => kind = found,
+                        Err(()) => return (world.t, kind, false, true),
+                    }
+                    if kind == events::EVENT_TIMEOUT && left <= window {
+                        world.t = world.time_limit;
+                        limited = true;
+                    }
+                }
+            }
+            (world.t, kind, limited, false)
+        })
+    }) {
+        Ok(r) => r,
+        Err(code) => return code,
+    };
+    unsafe {
+        *out_event_t = t;
+        *out_kind = kind;
+    }
+    if overflowed {
+        return set_world_error(handle, NUMERICAL_OVERFLOW, OVERFLOW_MESSAGE);
+    }
+    if limited {
+        return time_limit_error(handle);
+    }
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_snapshot_current_version() -> u32 {
+    archive::ARCHIVE_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn pl_snapshot_all(out_buf: *mut u8, buf_len: u32) -> u32 {
+    let links: Vec<(u64, SpringLink)> = match LINKS.lock() {
+        Ok(l) => l.iter().map(|(&id, &link)| (id, link)).collect(),
+        Err(_) => {
+            set_error(INTERNAL_ERROR, "failed to lock links");
+            return 0;
+        }
+    };
+    let worlds = match world_map() {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    // Links to destroyed worlds are inert; archiving them could revive them
+    // once a restore reuses the handle.
+    let live: Vec<(u64, SpringLink)> = links
+        .into_iter()
+        .filter(|(_, l)| worlds.contains_key(&l.a) && worlds.contains_key(&l.b))
+        .collect();
+    let bytes = archive::encode(worlds.iter().map(|(&handle, world)| (handle, world)), &live);
+    drop(worlds);
+    let needed = bytes.len() as u32;
+    if out_buf.is_null() || buf_len < needed {
+        clear_error();
+        return needed;
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buf, bytes.len());
+    }
+    clear_error();
+    needed
+}
+
+#[no_mangle]
+pub extern "C" fn pl_restore_all(buf: *const u8, buf_len: u32) -> i32 {
+    if buf.is_null() {
+        return set_error(INVALID_ARGUMENT, "buffer must be non-null");
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(buf, buf_len as usize) };
+    let (restored, restored_links) = match archive::decode(bytes) {
+        Ok(decoded) => decoded,
+        Err(message) => return set_error(INVALID_ARGUMENT, message),
+    };
+    // The archive's worlds replace the registry, so they alone must fit.
+    if let Err(code) = check_world_limit(restored.len()) {
+        return code;
+    }
+    let mut worlds = match world_map() {
+        Ok(m) => m,
+        Err(code) => return code,
+    };
+    // Links are replaced too, so no link from before the restore couples the
+    // restored worlds.
+    let mut links = match LINKS.lock() {
+        Ok(l) => l,
+        Err(_) => return set_error(INTERNAL_ERROR, "failed to lock links"),
+    };
+    worlds.clear();
+    let mut next = HANDLE_COUNTER.load(Ordering::SeqCst);
+    for (handle, world) in restored {
+        next = next.max(handle.saturating_add(1));
+        worlds.insert(handle, world);
+    }
+    HANDLE_COUNTER.store(next, Ordering::SeqCst);
+    links.clear();
+    let mut next_link = LINK_COUNTER.load(Ordering::SeqCst);
+    for (id, link) in restored_links {
+        next_link = next_link.max(id.saturating_add(1));
+        links.insert(id, link);
+    }
+    LINK_COUNTER.store(next_link, Ordering::SeqCst);
+    clear_error();
+    OK
+}
+
+#[no_mangle]
+pub extern "C" fn pl_world_ensemble(
+    cfg: *const PlWorldConfig,
+    base_seed: u64,
+    count: u32,
+    dt: f64,
+    steps: u32,
+    out_y: *mut f64,
+    out_vy: *mut f64,
+) -> i32 {
+    if cfg.is_null() || out_y.is_null() || out_vy.is_null() {
+        return set_error(INVALID_ARGUMENT, "pointers must be non-null");
+    }
+    if count == 0 {
+        return set_error(INVALID_ARGUMENT, "count must be > 0");
+    }
+    let cfg = unsafe { &*cfg };
+    if let Err(code) = validate_config(cfg) {
+        return code;
+    }
+    if let Err(code) = validate_dt(dt) {
+        return code;
+    }
+    if let Err(code) = validate_steps(steps) {
+        return code;
+    }
+    // Members never enter the registry: they live only for this call.
+    for i in 0..count {
+        let mut world = World::new(cfg.y0, cfg.vy0, cfg.g);
+        world.noise_amplitude = cfg.noise_amplitude;
+        world.rng_state = base_seed.wrapping_add(u64::from(i));
+        step_world(&mut world, dt, steps);
+        unsafe {
+            *out_y.add(i as usize) = world.y;
+            *out_vy.add(i as usize) = world.vy;
+        }
+    }
+    clear_error();
+    OK
 }
@@ -0,0 +1,35 @@
+// Protobuf encoding of a world's state, compatible with:
+//
+//   message PlState {
+//     double t = 1;
+//     double y = 2;
+//     double vy = 3;
+//     uint64 step_count = 4;
+//   }
+//
+// Every field is always written, in field order: tags 0x09, 0x11 and 0x19
+// each followed by 8 little-endian bytes (wire type 1), then tag 0x20 and a
+// base-128 varint (wire type 0). `t` is on the world's offset clock, like
+// every other reported state.
+
+use super::{reported_state, World};
+
+pub(crate) fn encode_state(world: &World) -> Vec<u8> {
+    let state = reported_state(world);
+    let mut buf = Vec::with_capacity(3 * 9 + 11);
+    for (field, value) in [(1u8, state.t), (2, state.y), (3, state.vy)] {
+        buf.push(field << 3 | 1);
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+    buf.push(4 << 3);
+    put_varint(&mut buf, world.step_count);
+    buf
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
@@ -0,0 +1,48 @@
+extern crate physicslab_kernel;
+
+use std::collections::HashSet;
+use std::thread;
+
+extern "C" {
+    fn pl_world_create(y0: f64, vy0: f64) -> u64;
+    fn pl_world_destroy(handle: u64);
+    fn pl_world_count() -> u64;
+}
+
+const THREADS: usize = 16;
+const WORLDS_PER_THREAD: usize = 2_000;
+
+#[test]
+fn concurrent_create_destroy_yields_unique_handles() {
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            thread::spawn(|| {
+                let mut handles = Vec::with_capacity(WORLDS_PER_THREAD);
+                for i in 0..WORLDS_PER_THREAD {
+                    let handle = unsafe { pl_world_create(i as f64, 0.0) };
+                    assert_ne!(handle, 0);
+                    handles.push(handle);
+                    // Interleave destroys so the registry churns under contention.
+                    if i % 2 == 1 {
+                        unsafe { pl_world_destroy(handles[i - 1]) };
+                    }
+                }
+                for (i, &handle) in handles.iter().enumerate() {
+                    if i % 2 == 1 || i + 1 == handles.len() {
+                        unsafe { pl_world_destroy(handle) };
+                    }
+                }
+                handles
+            })
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    for worker in workers {
+        for handle in worker.join().expect("worker panicked") {
+            assert!(seen.insert(handle), "handle {handle} issued twice");
+        }
+    }
+    assert_eq!(seen.len(), THREADS * WORLDS_PER_THREAD);
+    assert_eq!(unsafe { pl_world_count() }, 0);
+}
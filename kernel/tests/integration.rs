@@ -1,13 +1,209 @@
+extern crate physicslab_kernel;
+
+use std::sync::{Mutex, MutexGuard};
+
 const OK: i32 = 0;
 const INVALID_ARGUMENT: i32 = 1;
+const INVALID_HANDLE: i32 = 2;
+const POLICY_DENIED: i32 = 3;
+const CURSOR_END: i32 = 5;
+const IO_ERROR: i32 = 6;
+const TIME_LIMIT_REACHED: i32 = 7;
+const NUMERICAL_OVERFLOW: i32 = 8;
+const DETAIL_NONE: i32 = 0;
+const DETAIL_STEP_LIMIT: i32 = 1;
+const DETAIL_WORLD_LIMIT: i32 = 2;
+const EVENT_TIMEOUT: i32 = 0;
+const EVENT_GROUND: i32 = 1;
+const EVENT_APEX: i32 = 2;
+const FILTER_LANDED: i32 = 0;
+const FILTER_ASLEEP: i32 = 1;
+const FILTER_DIVERGED: i32 = 2;
+const FILTER_RISING: i32 = 3;
+const FORCE_GRAVITY: i32 = 0;
+const FORCE_REGIONS: i32 = 1;
+const FORCE_BUOYANCY: i32 = 2;
+
+// The kernel keeps its registry and last-error state in process globals, so
+// tests that inspect them must not interleave.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[repr(C)]
+struct PlWorldConfig {
+    y0: f64,
+    vy0: f64,
+    g: f64,
+    noise_amplitude: f64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct PlState {
+    t: f64,
+    y: f64,
+    vy: f64,
+}
 
-#[link(name = "physicslab_kernel", kind = "dylib")]
 extern "C" {
     fn pl_world_create(y0: f64, vy0: f64) -> u64;
     fn pl_world_destroy(handle: u64);
     fn pl_world_step(handle: u64, dt: f64, steps: u32) -> i32;
     fn pl_world_get_state(handle: u64, out_t: *mut f64, out_y: *mut f64, out_vy: *mut f64) -> i32;
+    fn pl_world_set_gravity_vector(handle: u64, gx: f64, gy: f64, gz: f64) -> i32;
+    fn pl_world_set_gravity_table(
+        handle: u64,
+        altitudes: *const f64,
+        gravities: *const f64,
+        count: u32,
+    ) -> i32;
+    fn pl_world_set_bounds(handle: u64, floor_y: f64, ceiling_y: f64, restitution: f64) -> i32;
+    fn pl_world_get_ground(
+        handle: u64,
+        out_ground_y: *mut f64,
+        out_restitution: *mut f64,
+        out_enabled: *mut i32,
+    ) -> i32;
+    fn pl_world_set_boundary_mode(handle: u64, mode: i32) -> i32;
+    fn pl_world_set_contact_epsilon(handle: u64, eps: f64) -> i32;
+    fn pl_world_set_time_limit(handle: u64, t_max: f64) -> i32;
+    fn pl_world_set_time_scale(handle: u64, scale: f64) -> i32;
+    fn pl_world_set_time_offset(handle: u64, offset: f64) -> i32;
+    fn pl_world_set_restitution_jitter(handle: u64, jitter: f64, seed: u64) -> i32;
+    fn pl_world_set_flush_denormals(handle: u64, enable: i32) -> i32;
+    fn pl_world_set_rollback(handle: u64, enable: i32) -> i32;
+    fn pl_world_set_noise(handle: u64, seed: u64, amplitude: f64) -> i32;
+    fn pl_seed_all(master_seed: u64) -> i32;
+    fn pl_world_ensemble(
+        cfg: *const PlWorldConfig,
+        base_seed: u64,
+        count: u32,
+        dt: f64,
+        steps: u32,
+        out_y: *mut f64,
+        out_vy: *mut f64,
+    ) -> i32;
+    fn pl_world_equals(handle_a: u64, handle_b: u64, tol: f64, out_equal: *mut i32) -> i32;
+    fn pl_world_step_count(handle: u64, out_count: *mut u64) -> i32;
+    fn pl_world_net_acceleration(handle: u64, out_a: *mut f64) -> i32;
+    fn pl_world_drag_force(handle: u64, out_f: *mut f64) -> i32;
+    fn pl_world_dominant_force(handle: u64, out_kind: *mut i32, out_magnitude: *mut f64) -> i32;
+    fn pl_world_integrator_order(handle: u64, out_order: *mut u32) -> i32;
+    fn pl_world_is_linear(handle: u64, out: *mut i32) -> i32;
+    fn pl_world_origin(
+        handle: u64,
+        out_launch_y: *mut f64,
+        out_launch_vy: *mut f64,
+        out_launch_t: *mut f64,
+    ) -> i32;
+    fn pl_world_check_stability(handle: u64, dt: f64, out_stable: *mut i32) -> i32;
+    fn pl_world_max_speed(handle: u64, out_v: *mut f64) -> i32;
+    fn pl_world_airborne_time(handle: u64, out_t: *mut f64) -> i32;
+    fn pl_world_energy_dissipated(handle: u64, out_joules: *mut f64) -> i32;
+    fn pl_world_has_diverged(handle: u64, out: *mut i32) -> i32;
+    fn pl_world_age_seconds(handle: u64, out_age: *mut f64) -> i32;
+    fn pl_world_last_step_nanos(handle: u64, out_ns: *mut u64) -> i32;
+    fn pl_world_get_state_struct(handle: u64, out: *mut PlState) -> i32;
+    fn pl_world_get_state_proto(handle: u64, out_buf: *mut u8, buf_len: u32) -> u32;
+    fn pl_world_add_body(handle: u64, y: f64, vy: f64, mass: f64, radius: f64) -> u64;
+    fn pl_world_get_body_state(handle: u64, body: u64, out_y: *mut f64, out_vy: *mut f64) -> i32;
+    fn pl_world_step_get(
+        handle: u64,
+        dt: f64,
+        steps: u32,
+        out_t: *mut f64,
+        out_y: *mut f64,
+        out_vy: *mut f64,
+    ) -> i32;
+    fn pl_world_get_true_state(handle: u64, out_t: *mut f64, out_y: *mut f64, out_vy: *mut f64) -> i32;
+    fn pl_world_set_measurement_noise(handle: u64, y_sigma: f64, vy_sigma: f64, seed: u64) -> i32;
+    fn pl_world_step_unchecked(handle: u64, dt: f64, steps: u32) -> i32;
+    #[cfg(debug_assertions)]
+    fn pl_world_step_trace(handle: u64, dt: f64, out_y: *mut f64, out_vy: *mut f64) -> i32;
+    fn pl_world_step_custom(
+        handle: u64,
+        dt: f64,
+        steps: u32,
+        accel_cb: Option<extern "C" fn(f64, f64, f64, *mut std::ffi::c_void) -> f64>,
+        user: *mut std::ffi::c_void,
+    ) -> i32;
+    fn pl_world_step_until(
+        handle: u64,
+        dt: f64,
+        max_steps: u32,
+        cond_cb: Option<extern "C" fn(f64, f64, f64, *mut std::ffi::c_void) -> i32>,
+        user: *mut std::ffi::c_void,
+        out_steps: *mut u32,
+    ) -> i32;
+    fn pl_world_step_with_error(handle: u64, dt: f64, steps: u32, out_error: *mut f64) -> i32;
+    fn pl_world_probe(
+        handle: u64,
+        dt: f64,
+        steps: u32,
+        out_t: *mut f64,
+        out_y: *mut f64,
+        out_vy: *mut f64,
+    ) -> i32;
+    fn pl_world_trajectory_to_file(handle: u64, dt: f64, steps: u32, path: *const u8, path_len: u32) -> i32;
+    fn pl_world_stream(
+        handle: u64,
+        dt: f64,
+        steps: u32,
+        batch: u32,
+        cb: Option<extern "C" fn(*const f64, *const f64, *const f64, u32, *mut std::ffi::c_void)>,
+        user: *mut std::ffi::c_void,
+    ) -> i32;
+    fn pl_link_spring(handle_a: u64, handle_b: u64, k: f64, rest_length: f64) -> u64;
+    fn pl_unlink(link_id: u64) -> i32;
+    fn pl_step_linked(dt: f64, steps: u32) -> i32;
+    fn pl_total_momentum(handles: *const u64, count: u32, out_p: *mut f64) -> i32;
+    fn pl_total_energy(handles: *const u64, count: u32, out_total: *mut f64) -> i32;
+    fn pl_world_trajectory_stats(
+        handle: u64,
+        dt: f64,
+        steps: u32,
+        out_min_y: *mut f64,
+        out_max_y: *mut f64,
+        out_mean_y: *mut f64,
+        out_final_vy: *mut f64,
+    ) -> i32;
+    fn pl_world_step_to_event(handle: u64, dt_max: f64, out_event_t: *mut f64, out_kind: *mut i32) -> i32;
+    fn pl_world_step_to_rest(handle: u64, dt: f64, v_eps: f64, a_eps: f64, out_steps: *mut u32) -> i32;
+    fn pl_snapshot_current_version() -> u32;
+    fn pl_snapshot_all(out_buf: *mut u8, buf_len: u32) -> u32;
+    fn pl_restore_all(buf: *const u8, buf_len: u32) -> i32;
     fn pl_last_error_code() -> i32;
+    fn pl_world_last_error_code(handle: u64) -> i32;
+    fn pl_world_last_error_message(handle: u64, out_buf: *mut u8, buf_len: u32) -> u32;
+    fn pl_last_error_message(out_buf: *mut u8, buf_len: u32) -> u32;
+    fn pl_last_error_message_len() -> u32;
+    fn pl_set_log_callback(cb: Option<extern "C" fn(code: i32, msg: *const u8, len: u32)>);
+    fn pl_last_error_detail() -> i32;
+    fn pl_reset_for_testing();
+    fn pl_world_destroy_batch(handles: *const u64, count: u32, out_status: *mut i32) -> i32;
+    fn pl_set_max_worlds(limit: u32) -> i32;
+    fn pl_get_max_worlds() -> u32;
+    fn pl_world_count() -> u64;
+    fn pl_world_cursor_begin() -> u64;
+    fn pl_world_cursor_next(cursor: u64, out_handle: *mut u64) -> i32;
+    fn pl_world_cursor_end(cursor: u64);
+    fn pl_handle_stats(out_min: *mut u64, out_max: *mut u64, out_next: *mut u64) -> i32;
+    fn pl_world_list(out_handles: *mut u64, cap: u32) -> u32;
+    fn pl_world_filter(predicate: i32, out_handles: *mut u64, cap: u32) -> u32;
+    fn pl_set_default_gravity(g: f64) -> i32;
+    fn pl_get_default_gravity() -> f64;
+    fn pl_get_defaults(out: *mut PlWorldConfig) -> i32;
+    fn pl_world_set_gravity(handle: u64, g: f64) -> i32;
+    fn pl_world_set_gravity_units(handle: u64, unit: i32, value: f64) -> i32;
+    fn pl_world_add_region(handle: u64, y_low: f64, y_high: f64, extra_accel: f64) -> i32;
+    fn pl_world_clear_regions(handle: u64) -> i32;
+    fn pl_world_set_buoyancy(handle: u64, fluid_density: f64, object_volume: f64, fluid_top_y: f64) -> i32;
+    fn pl_total_steps_executed() -> u64;
+    fn pl_world_schedule_gravity(handle: u64, at_t: f64, new_g: f64) -> i32;
+    fn pl_world_get_gravity(handle: u64, out_g: *mut f64) -> i32;
 }
 
 fn run_sim(y0: f64, vy0: f64, dt: f64, steps: u32) -> (f64, f64, f64) {
@@ -28,6 +224,7 @@ fn run_sim(y0: f64, vy0: f64, dt: f64, steps: u32) -> (f64, f64, f64) {
 
 #[test]
 fn determinism_same_inputs_same_outputs() {
+    let _guard = serial();
     let a = run_sim(10.0, 0.0, 0.1, 50);
     let b = run_sim(10.0, 0.0, 0.1, 50);
     assert!((a.0 - b.0).abs() < 1e-12);
@@ -37,6 +234,7 @@ fn determinism_same_inputs_same_outputs() {
 
 #[test]
 fn invalid_dt_rejected() {
+    let _guard = serial();
     unsafe {
         let handle = pl_world_create(0.0, 0.0);
         assert_ne!(handle, 0);
@@ -46,3 +244,2401 @@ fn invalid_dt_rejected() {
         pl_world_destroy(handle);
     }
 }
+
+#[test]
+fn step_error_estimate_scales_with_dt() {
+    let _guard = serial();
+    unsafe {
+        let coarse = pl_world_create(100.0, 5.0);
+        let fine = pl_world_create(100.0, 5.0);
+        let mut err_coarse = 0.0;
+        let mut err_fine = 0.0;
+        assert_eq!(pl_world_step_with_error(coarse, 0.02, 50, &mut err_coarse), OK);
+        assert_eq!(pl_world_step_with_error(fine, 0.01, 100, &mut err_fine), OK);
+        assert!(err_coarse > 0.0);
+        // Local error of the first-order step is O(dt^2).
+        let ratio = err_coarse / err_fine;
+        assert!((ratio - 4.0).abs() < 0.5, "ratio {ratio}");
+
+        let (t, y, vy) = run_sim(100.0, 5.0, 0.02, 50);
+        let mut t2 = 0.0;
+        let mut y2 = 0.0;
+        let mut vy2 = 0.0;
+        assert_eq!(pl_world_get_state(coarse, &mut t2, &mut y2, &mut vy2), OK);
+        assert_eq!((t, y, vy), (t2, y2, vy2));
+
+        assert_eq!(
+            pl_world_step_with_error(coarse, 0.01, 1, std::ptr::null_mut()),
+            INVALID_ARGUMENT
+        );
+        pl_world_destroy(coarse);
+        pl_world_destroy(fine);
+    }
+}
+
+#[test]
+fn default_gravity_seeds_new_worlds_only() {
+    let _guard = serial();
+    unsafe {
+        let before = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_get_default_gravity(), 9.81);
+        assert_eq!(pl_set_default_gravity(1.62), OK);
+        let after = pl_world_create(0.0, 0.0);
+        let mut g_before = 0.0;
+        let mut g_after = 0.0;
+        assert_eq!(pl_world_get_gravity(before, &mut g_before), OK);
+        assert_eq!(pl_world_get_gravity(after, &mut g_after), OK);
+        assert_eq!(g_before, 9.81);
+        assert_eq!(g_after, 1.62);
+        let mut defaults = PlWorldConfig {
+            y0: 1.0,
+            vy0: 1.0,
+            g: 0.0,
+            noise_amplitude: 1.0,
+        };
+        assert_eq!(pl_get_defaults(&mut defaults), OK);
+        assert_eq!(
+            (defaults.y0, defaults.vy0, defaults.g, defaults.noise_amplitude),
+            (0.0, 0.0, 1.62, 0.0)
+        );
+        assert_eq!(pl_get_defaults(std::ptr::null_mut()), INVALID_ARGUMENT);
+
+        assert_eq!(pl_set_default_gravity(f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_get_default_gravity(), 1.62);
+        assert_eq!(pl_world_set_gravity(after, f64::INFINITY), INVALID_ARGUMENT);
+
+        assert_eq!(pl_set_default_gravity(9.81), OK);
+        pl_world_destroy(before);
+        pl_world_destroy(after);
+    }
+}
+
+#[test]
+fn reset_for_testing_restarts_handles() {
+    let _guard = serial();
+    unsafe {
+        pl_world_create(1.0, 0.0);
+        pl_set_default_gravity(3.0);
+        pl_world_step(0, 0.1, 1);
+        pl_reset_for_testing();
+        assert_eq!(pl_world_count(), 0);
+        assert_eq!(pl_last_error_code(), OK);
+        assert_eq!(pl_get_default_gravity(), 9.81);
+        assert_eq!(pl_world_create(0.0, 0.0), 1);
+        assert_eq!(pl_world_create(0.0, 0.0), 2);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn step_get_matches_step_then_get_state() {
+    let _guard = serial();
+    let expected = run_sim(5.0, 2.0, 0.05, 40);
+    unsafe {
+        let handle = pl_world_create(5.0, 2.0);
+        let mut t = 0.0;
+        let mut y = 0.0;
+        let mut vy = 0.0;
+        assert_eq!(pl_world_step_get(handle, 0.05, 40, &mut t, &mut y, &mut vy), OK);
+        assert_eq!((t, y, vy), expected);
+        assert_eq!(
+            pl_world_step_get(handle, 0.05, 1, &mut t, std::ptr::null_mut(), &mut vy),
+            INVALID_ARGUMENT
+        );
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn step_matches_golden_bits() {
+    let _guard = serial();
+    // Recorded on x86_64; any platform must reproduce these bits exactly.
+    let (t, y, vy) = run_sim(10.0, 3.0, 0.01, 1000);
+    assert_eq!(t.to_bits(), 0x4023ffffffffffa1);
+    assert_eq!(y.to_bits(), 0xc07c2fd916872b40);
+    assert_eq!(vy.to_bits(), 0xc057c666666666c2);
+}
+
+#[test]
+fn step_limit_reports_detail() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_step(handle, 0.01, 10_001), POLICY_DENIED);
+        assert_eq!(pl_last_error_detail(), DETAIL_STEP_LIMIT);
+        assert_eq!(pl_world_step(handle, 0.0, 1), INVALID_ARGUMENT);
+        assert_eq!(pl_last_error_detail(), DETAIL_NONE);
+        pl_world_destroy(handle);
+    }
+}
+
+fn state_of(handle: u64) -> (f64, f64, f64) {
+    let mut t = 0.0;
+    let mut y = 0.0;
+    let mut vy = 0.0;
+    assert_eq!(unsafe { pl_world_get_state(handle, &mut t, &mut y, &mut vy) }, OK);
+    (t, y, vy)
+}
+
+#[test]
+fn snapshot_all_round_trips_registry() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(10.0, 1.0);
+        let b = pl_world_create(-3.0, 2.5);
+        pl_world_set_gravity(b, 1.62);
+        pl_world_step(a, 0.1, 7);
+        let expected_a = state_of(a);
+        let expected_b = state_of(b);
+
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        assert_eq!(pl_snapshot_all(buf.as_mut_ptr(), needed), needed);
+
+        pl_world_destroy(a);
+        pl_world_create(0.0, 0.0);
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), OK);
+        assert_eq!(pl_world_count(), 2);
+        assert_eq!(state_of(a), expected_a);
+        assert_eq!(state_of(b), expected_b);
+        let mut g = 0.0;
+        pl_world_get_gravity(b, &mut g);
+        assert_eq!(g, 1.62);
+        let fresh = pl_world_create(0.0, 0.0);
+        assert!(fresh > b);
+
+        buf[0] ^= 0xff;
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), INVALID_ARGUMENT);
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed - 1), INVALID_ARGUMENT);
+        assert_eq!(pl_world_count(), 3);
+        pl_reset_for_testing();
+    }
+}
+
+fn noisy_run(seed: u64) -> (f64, f64, f64) {
+    unsafe {
+        let handle = pl_world_create(50.0, 0.0);
+        assert_eq!(pl_world_set_noise(handle, seed, 2.0), OK);
+        pl_world_step(handle, 0.01, 500);
+        let state = state_of(handle);
+        pl_world_destroy(handle);
+        state
+    }
+}
+
+#[test]
+fn seeded_noise_is_reproducible() {
+    let _guard = serial();
+    let clean = run_sim(50.0, 0.0, 0.01, 500);
+    let a = noisy_run(42);
+    assert_eq!(a, noisy_run(42));
+    assert_ne!(a, noisy_run(43));
+    assert_ne!(a, clean);
+    // Noise is bounded by the amplitude, so the drift is too.
+    assert!((a.2 - clean.2).abs() <= 2.0 * 5.0);
+    unsafe {
+        let handle = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_set_noise(handle, 1, -1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_noise(handle, 1, f64::NAN), INVALID_ARGUMENT);
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn ensemble_members_match_individual_worlds() {
+    let _guard = serial();
+    let cfg = PlWorldConfig {
+        y0: 50.0,
+        vy0: 0.0,
+        g: 9.81,
+        noise_amplitude: 2.0,
+    };
+    let mut ys = [0.0; 4];
+    let mut vys = [0.0; 4];
+    unsafe {
+        let count_before = pl_world_count();
+        assert_eq!(
+            pl_world_ensemble(&cfg, 40, 4, 0.01, 500, ys.as_mut_ptr(), vys.as_mut_ptr()),
+            OK
+        );
+        assert_eq!(pl_world_count(), count_before);
+    }
+    let member = noisy_run(42);
+    assert_eq!((ys[2], vys[2]), (member.1, member.2));
+    assert_ne!(ys[0], ys[1]);
+
+    let bad = PlWorldConfig {
+        noise_amplitude: -1.0,
+        ..cfg
+    };
+    unsafe {
+        assert_eq!(
+            pl_world_ensemble(&bad, 0, 4, 0.01, 10, ys.as_mut_ptr(), vys.as_mut_ptr()),
+            INVALID_ARGUMENT
+        );
+    }
+}
+
+#[test]
+fn state_struct_matches_pointer_getter() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(3.0, 4.0);
+        pl_world_step(handle, 0.1, 3);
+        let mut state = PlState::default();
+        assert_eq!(pl_world_get_state_struct(handle, &mut state), OK);
+        assert_eq!((state.t, state.y, state.vy), state_of(handle));
+        assert_eq!(pl_world_get_state_struct(handle, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn gravity_vector_uses_vertical_component() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_set_gravity_vector(handle, 1.0, -3.7, 2.0), OK);
+        let mut g = 0.0;
+        pl_world_get_gravity(handle, &mut g);
+        assert_eq!(g, 3.7);
+        assert_eq!(pl_world_set_gravity_vector(handle, f64::NAN, -3.7, 0.0), INVALID_ARGUMENT);
+        pl_world_get_gravity(handle, &mut g);
+        assert_eq!(g, 3.7);
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn step_world_matches_ffi_step() {
+    let _guard = serial();
+    let mut world = physicslab_kernel::World::new(10.0, 3.0, 9.81);
+    physicslab_kernel::step_world(&mut world, 0.01, 1000);
+    let state = world.state();
+    assert_eq!((state.t, state.y, state.vy), run_sim(10.0, 3.0, 0.01, 1000));
+}
+
+fn accel_after_one_step(handle: u64, dt: f64) -> f64 {
+    let (_, _, vy_before) = state_of(handle);
+    unsafe { pl_world_step(handle, dt, 1) };
+    let (_, _, vy_after) = state_of(handle);
+    (vy_after - vy_before) / dt
+}
+
+#[test]
+fn gravity_table_interpolates_and_clamps() {
+    let _guard = serial();
+    let altitudes = [0.0, 100.0, 200.0];
+    let gravities = [10.0, 8.0, 4.0];
+    unsafe {
+        let mid = pl_world_create(50.0, 0.0);
+        let below = pl_world_create(-20.0, 0.0);
+        let above = pl_world_create(500.0, 0.0);
+        for h in [mid, below, above] {
+            assert_eq!(
+                pl_world_set_gravity_table(h, altitudes.as_ptr(), gravities.as_ptr(), 3),
+                OK
+            );
+        }
+        assert!((accel_after_one_step(mid, 1e-6) + 9.0).abs() < 1e-6);
+        assert!((accel_after_one_step(below, 1e-6) + 10.0).abs() < 1e-6);
+        assert!((accel_after_one_step(above, 1e-6) + 4.0).abs() < 1e-6);
+
+        let unsorted = [0.0, 100.0, 100.0];
+        assert_eq!(
+            pl_world_set_gravity_table(mid, unsorted.as_ptr(), gravities.as_ptr(), 3),
+            INVALID_ARGUMENT
+        );
+        assert_eq!(pl_world_set_gravity_table(mid, std::ptr::null(), std::ptr::null(), 0), OK);
+        assert!((accel_after_one_step(mid, 1e-6) + 9.81).abs() < 1e-6);
+        for h in [mid, below, above] {
+            pl_world_destroy(h);
+        }
+    }
+}
+
+static LOGGED: Mutex<Vec<(i32, String)>> = Mutex::new(Vec::new());
+
+extern "C" fn record_log(code: i32, msg: *const u8, len: u32) {
+    let bytes = unsafe { std::slice::from_raw_parts(msg, len as usize) };
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    LOGGED.lock().unwrap().push((code, text));
+}
+
+#[test]
+fn log_callback_sees_every_error() {
+    let _guard = serial();
+    unsafe {
+        pl_set_log_callback(Some(record_log));
+        let handle = pl_world_create(0.0, 0.0);
+        pl_world_step(handle, 0.0, 1);
+        pl_world_step(handle, 0.1, 1);
+        pl_world_step(0, 0.1, 1);
+        pl_set_log_callback(None);
+        pl_world_step(0, 0.1, 1);
+        pl_world_destroy(handle);
+    }
+    let logged = std::mem::take(&mut *LOGGED.lock().unwrap());
+    assert_eq!(
+        logged,
+        vec![
+            (INVALID_ARGUMENT, "dt must be positive".to_string()),
+            (INVALID_HANDLE, "invalid handle".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn bubble_bounces_between_floor_and_ceiling() {
+    let _guard = serial();
+    unsafe {
+        let bubble = pl_world_create(0.5, 0.0);
+        assert_eq!(pl_world_set_gravity(bubble, -2.0), OK);
+        assert_eq!(pl_world_set_bounds(bubble, 0.0, 1.0, 1.0), OK);
+        let mut hit_ceiling = false;
+        let mut hit_floor = false;
+        let mut last_vy = 0.0;
+        for _ in 0..2000 {
+            pl_world_step(bubble, 0.01, 1);
+            let (_, y, vy) = state_of(bubble);
+            assert!((0.0..=1.0).contains(&y), "escaped to {y}");
+            hit_ceiling |= last_vy > 0.0 && vy < 0.0;
+            hit_floor |= last_vy < 0.0 && vy > 0.0;
+            last_vy = vy;
+        }
+        assert!(hit_ceiling);
+        assert!(hit_floor);
+        assert_eq!(pl_world_set_bounds(bubble, 1.0, 1.0, 0.5), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_bounds(bubble, 0.0, 1.0, 1.5), INVALID_ARGUMENT);
+        pl_world_destroy(bubble);
+    }
+}
+
+#[test]
+fn last_step_nanos_tracks_most_recent_step() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(0.0, 0.0);
+        let mut ns = u64::MAX;
+        assert_eq!(pl_world_last_step_nanos(handle, &mut ns), OK);
+        assert_eq!(ns, 0);
+        pl_world_step(handle, 0.001, 10_000);
+        assert_eq!(pl_world_last_step_nanos(handle, &mut ns), OK);
+        assert!(ns > 0);
+        assert_eq!(pl_world_last_step_nanos(handle, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(handle);
+        assert_eq!(pl_world_last_step_nanos(handle, &mut ns), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn every_stepping_call_records_its_step_time() {
+    let _guard = serial();
+    let mut k = 4.0f64;
+    let user = &mut k as *mut f64 as *mut std::ffi::c_void;
+    let mut err = 0.0;
+    unsafe {
+        let worlds: Vec<u64> = (0..3).map(|_| pl_world_create(1.0, 0.0)).collect();
+        assert_eq!(pl_world_step_unchecked(worlds[0], 1e-4, 10_000), OK);
+        assert_eq!(pl_world_step_custom(worlds[1], 1e-4, 10_000, Some(spring_accel), user), OK);
+        assert_eq!(pl_world_step_with_error(worlds[2], 1e-4, 10_000, &mut err), OK);
+        for h in worlds {
+            let mut ns = 0;
+            pl_world_last_step_nanos(h, &mut ns);
+            assert!(ns > 0, "world {h}");
+            pl_world_destroy(h);
+        }
+    }
+}
+
+#[test]
+fn multi_world_walks_are_sorted_by_handle() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let handles: Vec<u64> = (0..20).map(|i| pl_world_create(i as f64, 0.0)).collect();
+        for &h in handles.iter().step_by(3) {
+            pl_world_destroy(h);
+        }
+        let live = pl_world_list(std::ptr::null_mut(), 0);
+        let mut listed = vec![0u64; live as usize];
+        assert_eq!(pl_world_list(listed.as_mut_ptr(), live), live);
+        let mut sorted = listed.clone();
+        sorted.sort_unstable();
+        assert_eq!(listed, sorted);
+
+        let mut first_two = [0u64; 2];
+        assert_eq!(pl_world_list(first_two.as_mut_ptr(), 2), live);
+        assert_eq!(first_two, [listed[0], listed[1]]);
+
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        pl_snapshot_all(buf.as_mut_ptr(), needed);
+        // Current record size for a world with no bounds, table or bodies;
+        // the archive ends with an empty link list.
+        const PLAIN_RECORD_LEN: usize = 190;
+        let archived: Vec<u64> = buf[12..buf.len() - 4]
+            .chunks(PLAIN_RECORD_LEN)
+            .map(|record| u64::from_le_bytes(record[..8].try_into().unwrap()))
+            .collect();
+        assert_eq!(archived, listed);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn bounds_place_out_of_range_bodies_at_rest() {
+    let _guard = serial();
+    unsafe {
+        let at = pl_world_create(0.0, -1.0);
+        let below = pl_world_create(-5.0, -3.0);
+        let above = pl_world_create(2.0, 1.5);
+        for h in [at, below, above] {
+            assert_eq!(pl_world_set_bounds(h, 0.0, 10.0, 0.5), OK);
+        }
+        assert_eq!(state_of(at), (0.0, 0.0, -1.0));
+        assert_eq!(state_of(below), (0.0, 0.0, 0.0));
+        assert_eq!(state_of(above), (0.0, 2.0, 1.5));
+
+        let ceiling = pl_world_create(12.0, 4.0);
+        assert_eq!(pl_world_set_bounds(ceiling, 0.0, 10.0, 0.5), OK);
+        assert_eq!(state_of(ceiling), (0.0, 10.0, 0.0));
+
+        for h in [at, below, above, ceiling] {
+            pl_world_step(h, 0.01, 500);
+            let (_, y, _) = state_of(h);
+            assert!((0.0..=10.0).contains(&y));
+            pl_world_destroy(h);
+        }
+    }
+}
+
+#[test]
+fn measurement_noise_leaves_true_state_clean() {
+    let _guard = serial();
+    let clean = run_sim(100.0, 0.0, 0.01, 100);
+    unsafe {
+        let handle = pl_world_create(100.0, 0.0);
+        assert_eq!(pl_world_set_measurement_noise(handle, 0.5, 0.0, 7), OK);
+        pl_world_step(handle, 0.01, 100);
+        let mut t = 0.0;
+        let mut y = 0.0;
+        let mut vy = 0.0;
+        assert_eq!(pl_world_get_true_state(handle, &mut t, &mut y, &mut vy), OK);
+        assert_eq!((t, y, vy), clean);
+
+        let n = 2000;
+        let mut sum = 0.0;
+        let mut sum_sq = 0.0;
+        for _ in 0..n {
+            let (t_noisy, y_noisy, vy_noisy) = state_of(handle);
+            assert_eq!((t_noisy, vy_noisy), (clean.0, clean.2));
+            let e = y_noisy - clean.1;
+            sum += e;
+            sum_sq += e * e;
+        }
+        let mean = sum / n as f64;
+        let sigma = (sum_sq / n as f64 - mean * mean).sqrt();
+        assert!(mean.abs() < 0.05, "mean {mean}");
+        assert!((sigma - 0.5).abs() < 0.05, "sigma {sigma}");
+
+        assert_eq!(pl_world_set_measurement_noise(handle, -0.1, 0.0, 7), INVALID_ARGUMENT);
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn get_state_skips_null_outputs() {
+    let _guard = serial();
+    let (t, y, vy) = run_sim(4.0, 1.0, 0.1, 5);
+    let null = std::ptr::null_mut::<f64>();
+    unsafe {
+        let handle = pl_world_create(4.0, 1.0);
+        pl_world_step(handle, 0.1, 5);
+        let sentinel = -12345.0;
+        let mut y_only = sentinel;
+        assert_eq!(pl_world_get_state(handle, null, &mut y_only, null), OK);
+        assert_eq!(y_only, y);
+
+        let mut t_out = sentinel;
+        let mut vy_out = sentinel;
+        assert_eq!(pl_world_get_state(handle, &mut t_out, null, &mut vy_out), OK);
+        assert_eq!((t_out, vy_out), (t, vy));
+
+        assert_eq!(pl_world_get_state(handle, null, null, null), INVALID_ARGUMENT);
+        assert_eq!(pl_world_get_state(0, null, &mut y_only, null), INVALID_HANDLE);
+        pl_world_destroy(handle);
+        assert_eq!(pl_world_get_state(handle, null, &mut y_only, null), INVALID_HANDLE);
+        assert_eq!(y_only, y);
+    }
+}
+
+#[test]
+fn step_count_accumulates_across_mixed_dt() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(0.0, 0.0);
+        let mut count = u64::MAX;
+        assert_eq!(pl_world_step_count(handle, &mut count), OK);
+        assert_eq!(count, 0);
+        pl_world_step(handle, 0.1, 10);
+        pl_world_step(handle, 0.4, 5);
+        assert_eq!(pl_world_step_count(handle, &mut count), OK);
+        assert_eq!(count, 15);
+        let (t, _, _) = state_of(handle);
+        assert!((t / count as f64 - 0.2).abs() < 1e-12);
+        pl_world_destroy(handle);
+    }
+}
+
+#[test]
+fn time_scale_rescales_physics_dt() {
+    let _guard = serial();
+    let reference = run_sim(20.0, 0.0, 0.005, 200);
+    unsafe {
+        let slow = pl_world_create(20.0, 0.0);
+        assert_eq!(pl_world_set_time_scale(slow, 0.5), OK);
+        pl_world_step(slow, 0.01, 200);
+        assert_eq!(state_of(slow), reference);
+
+        let paused = pl_world_create(20.0, 3.0);
+        assert_eq!(pl_world_set_time_scale(paused, 0.0), OK);
+        pl_world_step(paused, 0.01, 200);
+        assert_eq!(state_of(paused), (0.0, 20.0, 3.0));
+
+        assert_eq!(pl_world_set_time_scale(paused, -1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_time_scale(paused, f64::INFINITY), INVALID_ARGUMENT);
+        pl_world_destroy(slow);
+        pl_world_destroy(paused);
+    }
+}
+
+#[test]
+fn handle_stats_report_live_range_and_next() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let (mut min, mut max, mut next) = (u64::MAX, u64::MAX, u64::MAX);
+        assert_eq!(pl_handle_stats(&mut min, &mut max, &mut next), OK);
+        assert_eq!((min, max, next), (0, 0, 1));
+        let a = pl_world_create(0.0, 0.0);
+        let b = pl_world_create(0.0, 0.0);
+        let c = pl_world_create(0.0, 0.0);
+        pl_world_destroy(a);
+        assert_eq!(pl_handle_stats(&mut min, &mut max, &mut next), OK);
+        assert_eq!((min, max, next), (b, c, c + 1));
+        assert_eq!(
+            pl_handle_stats(std::ptr::null_mut(), std::ptr::null_mut(), &mut next),
+            OK
+        );
+        pl_reset_for_testing();
+    }
+}
+
+fn bounce_speeds(jitter: f64, seed: u64) -> Vec<f64> {
+    let mut speeds = Vec::new();
+    unsafe {
+        let ball = pl_world_create(5.0, 0.0);
+        pl_world_set_bounds(ball, 0.0, 100.0, 0.8);
+        assert_eq!(pl_world_set_restitution_jitter(ball, jitter, seed), OK);
+        let mut last_vy = 0.0;
+        for _ in 0..3000 {
+            pl_world_step(ball, 0.001, 1);
+            let (_, _, vy) = state_of(ball);
+            if last_vy < 0.0 && vy > 0.0 {
+                speeds.push(vy);
+            }
+            last_vy = vy;
+        }
+        pl_world_destroy(ball);
+    }
+    speeds
+}
+
+#[test]
+fn restitution_jitter_is_seeded() {
+    let _guard = serial();
+    let plain = bounce_speeds(0.0, 0);
+    let a = bounce_speeds(0.1, 9);
+    assert!(a.len() >= 2);
+    assert_eq!(a, bounce_speeds(0.1, 9));
+    assert_ne!(a, bounce_speeds(0.1, 10));
+    assert_ne!(a[..2], plain[..2]);
+    unsafe {
+        let h = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_set_restitution_jitter(h, 1.5, 0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_restitution_jitter(h, -0.1, 0), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+    }
+}
+
+#[test]
+fn state_proto_uses_documented_wire_format() {
+    let _guard = serial();
+    unsafe {
+        let handle = pl_world_create(2.0, 1.0);
+        pl_world_step(handle, 0.25, 200);
+        let (t, y, vy) = state_of(handle);
+        let needed = pl_world_get_state_proto(handle, std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        assert_eq!(pl_world_get_state_proto(handle, buf.as_mut_ptr(), needed), needed);
+        let mut expected = Vec::new();
+        for (tag, value) in [(0x09u8, t), (0x11, y), (0x19, vy)] {
+            expected.push(tag);
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        // step_count = 200 as a varint: 0xc8 0x01.
+        expected.extend_from_slice(&[0x20, 0xc8, 0x01]);
+        assert_eq!(buf, expected);
+        let mut short = [0xffu8; 12];
+        assert_eq!(pl_world_get_state_proto(handle, short.as_mut_ptr(), 10), needed);
+        assert_eq!(short[..10], expected[..10]);
+        assert_eq!(short[10..], [0xff, 0xff]);
+        pl_world_destroy(handle);
+        assert_eq!(pl_world_get_state_proto(handle, buf.as_mut_ptr(), needed), 0);
+        assert_eq!(pl_last_error_code(), INVALID_HANDLE);
+    }
+}
+
+fn body_state(handle: u64, body: u64) -> (f64, f64) {
+    let mut y = 0.0;
+    let mut vy = 0.0;
+    assert_eq!(unsafe { pl_world_get_body_state(handle, body, &mut y, &mut vy) }, OK);
+    (y, vy)
+}
+
+#[test]
+fn bodies_collide_elastically() {
+    let _guard = serial();
+    unsafe {
+        let world = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(world, 0.0);
+        let lower = pl_world_add_body(world, 0.0, 2.0, 1.0, 0.1);
+        let upper = pl_world_add_body(world, 1.0, -1.0, 3.0, 0.1);
+        assert_eq!((lower, upper), (1, 2));
+        // m1 = 1 at +2 and m2 = 3 at -1.
+        let momentum = 2.0 - 3.0;
+        let energy = 2.0 + 1.5;
+        pl_world_step(world, 0.01, 100);
+        let (_, v1) = body_state(world, lower);
+        let (_, v2) = body_state(world, upper);
+        assert!((1.0 * v1 + 3.0 * v2 - momentum).abs() < 1e-12);
+        assert!((0.5 * v1 * v1 + 1.5 * v2 * v2 - energy).abs() < 1e-12);
+        assert!((v1 + 2.5).abs() < 1e-12 && (v2 - 0.5).abs() < 1e-12);
+
+        // Newton's cradle: a strike on a resting row ends up in the far ball.
+        let cradle = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(cradle, 0.0);
+        let first = pl_world_add_body(cradle, -0.5, 1.0, 1.0, 0.1);
+        let rest: Vec<u64> = (0..3)
+            .map(|i| pl_world_add_body(cradle, 0.2 * i as f64, 0.0, 1.0, 0.1))
+            .collect();
+        pl_world_step(cradle, 0.01, 40);
+        assert_eq!(body_state(cradle, first).1, 0.0);
+        assert_eq!(body_state(cradle, rest[0]).1, 0.0);
+        assert_eq!(body_state(cradle, rest[1]).1, 0.0);
+        assert_eq!(body_state(cradle, rest[2]).1, 1.0);
+
+        let mut y = 0.0;
+        let mut vy = 0.0;
+        assert_eq!(pl_world_get_body_state(world, 3, &mut y, &mut vy), INVALID_ARGUMENT);
+        assert_eq!(pl_world_add_body(world, 0.0, 0.0, 0.0, 0.1), 0);
+        pl_world_destroy(world);
+        pl_world_destroy(cradle);
+    }
+}
+
+fn archive_header(version: u32, count: u32) -> Vec<u8> {
+    let mut blob = b"PLAR".to_vec();
+    blob.extend_from_slice(&version.to_le_bytes());
+    blob.extend_from_slice(&count.to_le_bytes());
+    blob
+}
+
+#[test]
+fn restore_all_reads_version_1_with_defaults() {
+    let _guard = serial();
+    assert_eq!(unsafe { pl_snapshot_current_version() }, 23);
+    let mut blob = archive_header(1, 1);
+    blob.extend_from_slice(&7u64.to_le_bytes());
+    for value in [1.5f64, 20.0, -2.0, 3.0] {
+        blob.extend_from_slice(&value.to_le_bytes());
+    }
+    unsafe {
+        pl_reset_for_testing();
+        assert_eq!(pl_restore_all(blob.as_ptr(), blob.len() as u32), OK);
+        assert_eq!(state_of(7), (1.5, 20.0, -2.0));
+        let mut g = 0.0;
+        pl_world_get_gravity(7, &mut g);
+        assert_eq!(g, 3.0);
+        let mut count = u64::MAX;
+        pl_world_step_count(7, &mut count);
+        assert_eq!(count, 0);
+        // Defaults: unit time scale, no noise, no bounds.
+        let expected = {
+            let mut world = physicslab_kernel::World::new(20.0, -2.0, 3.0);
+            physicslab_kernel::step_world(&mut world, 0.1, 1000);
+            world.state()
+        };
+        pl_world_step(7, 0.1, 1000);
+        let (_, y, vy) = state_of(7);
+        assert_eq!((y, vy), (expected.y, expected.vy));
+
+        let newer = archive_header(24, 0);
+        assert_eq!(pl_restore_all(newer.as_ptr(), newer.len() as u32), INVALID_ARGUMENT);
+        let mut msg = [0u8; 64];
+        let len = pl_last_error_message(msg.as_mut_ptr(), msg.len() as u32) as usize;
+        assert_eq!(&msg[..len], b"archive version is newer than this kernel");
+        assert_eq!(pl_world_count(), 1);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn restore_all_reads_version_8_with_defaults() {
+    let _guard = serial();
+    let f64s = |blob: &mut Vec<u8>, values: &[f64]| {
+        for value in values {
+            blob.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    // A world as version 8 wrote it, before bodies were archived: no table
+    // or bounds, 42 steps taken and a time scale of 2.
+    let mut blob = archive_header(8, 1);
+    blob.extend_from_slice(&4u64.to_le_bytes());
+    f64s(&mut blob, &[0.0, 30.0, 0.0, 2.0, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.push(0);
+    f64s(&mut blob, &[0.0, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    blob.extend_from_slice(&42u64.to_le_bytes());
+    f64s(&mut blob, &[2.0, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    unsafe {
+        pl_reset_for_testing();
+        assert_eq!(pl_restore_all(blob.as_ptr(), blob.len() as u32), OK);
+        assert_eq!(state_of(4), (0.0, 30.0, 0.0));
+        let mut count = 0;
+        pl_world_step_count(4, &mut count);
+        assert_eq!(count, 42);
+        pl_world_step(4, 0.1, 1);
+        assert_eq!(state_of(4).0, 0.2);
+        // Bodies default to none, so the first one added gets index 1.
+        assert_eq!(pl_world_add_body(4, 10.0, 0.0, 1.0, 0.5), 1);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn restore_all_reads_version_19_with_defaults() {
+    let _guard = serial();
+    let f64s = |blob: &mut Vec<u8>, values: &[f64]| {
+        for value in values {
+            blob.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    // A plain world as version 19 wrote it: no table, bounds, bodies,
+    // schedule or regions, and a time offset of 5.
+    let mut blob = archive_header(19, 1);
+    blob.extend_from_slice(&9u64.to_le_bytes());
+    f64s(&mut blob, &[2.0, 1.0e6, 0.0, 1e308, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.push(0);
+    f64s(&mut blob, &[0.0, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    blob.extend_from_slice(&3u64.to_le_bytes());
+    f64s(&mut blob, &[1.0, 0.0]);
+    blob.extend_from_slice(&0u64.to_le_bytes());
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.push(0);
+    f64s(&mut blob, &[0.0, 0.0, f64::INFINITY]);
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.push(0);
+    f64s(&mut blob, &[0.0]);
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    f64s(&mut blob, &[5.0]);
+    blob.extend_from_slice(&1u64.to_le_bytes());
+    unsafe {
+        pl_reset_for_testing();
+        assert_eq!(pl_restore_all(blob.as_ptr(), blob.len() as u32), OK);
+        assert_eq!(state_of(9), (7.0, 1.0e6, 0.0));
+        let mut count = 0;
+        pl_world_step_count(9, &mut count);
+        assert_eq!(count, 3);
+        let mut diverged = 1;
+        pl_world_has_diverged(9, &mut diverged);
+        assert_eq!(diverged, 0);
+        // Rollback defaults to off: the overflowing step is kept.
+        assert_eq!(pl_world_step(9, 1.0, 2), OK);
+        assert!(!state_of(9).2.is_finite());
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn snapshot_all_round_trips_world_parameters() {
+    let _guard = serial();
+    let altitudes = [0.0, 50.0];
+    let gravities = [9.0, 7.0];
+    unsafe {
+        pl_reset_for_testing();
+        let rich = pl_world_create(10.0, 0.0);
+        pl_world_set_bounds(rich, 0.0, 40.0, 0.7);
+        pl_world_set_gravity_table(rich, altitudes.as_ptr(), gravities.as_ptr(), 2);
+        pl_world_set_noise(rich, 5, 0.3);
+        pl_world_set_restitution_jitter(rich, 0.2, 6);
+        pl_world_set_time_scale(rich, 1.5);
+        pl_world_add_body(rich, 20.0, 1.0, 2.0, 0.5);
+        pl_world_set_measurement_noise(rich, 0.05, 0.1, 8);
+        pl_world_set_buoyancy(rich, 1.0, 1.5, 5.0);
+        pl_world_step(rich, 0.01, 300);
+
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        pl_snapshot_all(buf.as_mut_ptr(), needed);
+
+        pl_world_step(rich, 0.01, 700);
+        let expected = (state_of(rich), body_state(rich, 1));
+
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), OK);
+        pl_world_step(rich, 0.01, 700);
+        assert_eq!((state_of(rich), body_state(rich, 1)), expected);
+        let mut count = 0;
+        pl_world_step_count(rich, &mut count);
+        assert_eq!(count, 1000);
+
+        buf.push(0);
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed + 1), INVALID_ARGUMENT);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn cursor_walks_handles_snapshotted_at_begin() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let handles: Vec<u64> = (0..4).map(|_| pl_world_create(0.0, 0.0)).collect();
+        let cursor = pl_world_cursor_begin();
+        assert_ne!(cursor, 0);
+        pl_world_destroy(handles[2]);
+        pl_world_create(0.0, 0.0);
+        let mut seen = Vec::new();
+        let mut handle = 0;
+        loop {
+            match pl_world_cursor_next(cursor, &mut handle) {
+                OK => seen.push(handle),
+                CURSOR_END => break,
+                other => panic!("unexpected status {other}"),
+            }
+        }
+        assert_eq!(seen, handles);
+        assert_eq!(pl_world_cursor_next(cursor, &mut handle), CURSOR_END);
+        pl_world_cursor_end(cursor);
+        assert_eq!(pl_last_error_code(), OK);
+        assert_eq!(pl_world_cursor_next(cursor, &mut handle), INVALID_HANDLE);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn flush_denormals_snaps_tiny_values_to_zero() {
+    let _guard = serial();
+    let tiny = f64::MIN_POSITIVE / 4.0;
+    unsafe {
+        let plain = pl_world_create(tiny, tiny);
+        let flushed = pl_world_create(tiny, tiny);
+        for h in [plain, flushed] {
+            pl_world_set_gravity(h, 0.0);
+        }
+        assert_eq!(pl_world_set_flush_denormals(flushed, 1), OK);
+        pl_world_step(plain, 1e-3, 1);
+        pl_world_step(flushed, 1e-3, 1);
+        let (_, y, vy) = state_of(plain);
+        assert!(y.is_subnormal() && vy.is_subnormal());
+        let (_, y, vy) = state_of(flushed);
+        assert_eq!((y, vy), (0.0, 0.0));
+        assert_eq!(pl_world_set_flush_denormals(0, 1), INVALID_HANDLE);
+        pl_world_destroy(plain);
+        pl_world_destroy(flushed);
+    }
+}
+
+#[test]
+fn destroy_batch_reports_per_handle_status() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(0.0, 0.0);
+        let b = pl_world_create(0.0, 0.0);
+        let keep = pl_world_create(0.0, 0.0);
+        let handles = [a, 0, b, 999, a];
+        let mut status = [-1i32; 5];
+        let rc = pl_world_destroy_batch(handles.as_ptr(), 5, status.as_mut_ptr());
+        assert_eq!(rc, INVALID_HANDLE);
+        assert_eq!(status, [OK, INVALID_HANDLE, OK, INVALID_HANDLE, INVALID_HANDLE]);
+        assert_eq!(pl_world_count(), 1);
+
+        let c = pl_world_create(0.0, 0.0);
+        let rest = [keep, c];
+        assert_eq!(pl_world_destroy_batch(rest.as_ptr(), 2, std::ptr::null_mut()), OK);
+        assert_eq!(pl_world_count(), 0);
+        let null = std::ptr::null_mut();
+        assert_eq!(pl_world_destroy_batch(std::ptr::null(), 1, null), INVALID_ARGUMENT);
+        assert_eq!(pl_world_destroy_batch(std::ptr::null(), 0, null), OK);
+    }
+}
+
+#[test]
+fn is_linear_tracks_nonlinear_forces() {
+    let _guard = serial();
+    let linear = |h: u64| {
+        let mut out = -1;
+        assert_eq!(unsafe { pl_world_is_linear(h, &mut out) }, OK);
+        out
+    };
+    unsafe {
+        let h = pl_world_create(1.0, 0.0);
+        pl_world_set_measurement_noise(h, 0.5, 0.5, 1);
+        assert_eq!(linear(h), 1);
+        pl_world_set_noise(h, 1, 0.1);
+        assert_eq!(linear(h), 0);
+        pl_world_set_noise(h, 1, 0.0);
+        pl_world_set_bounds(h, 0.0, 10.0, 1.0);
+        assert_eq!(linear(h), 0);
+
+        let t = pl_world_create(1.0, 0.0);
+        let (alt, g) = ([0.0, 10.0], [9.8, 9.7]);
+        pl_world_set_gravity_table(t, alt.as_ptr(), g.as_ptr(), 2);
+        assert_eq!(linear(t), 0);
+        pl_world_set_gravity_table(t, std::ptr::null(), std::ptr::null(), 0);
+        assert_eq!(linear(t), 1);
+
+        assert_eq!(pl_world_is_linear(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        pl_world_destroy(t);
+        let mut out = 0;
+        assert_eq!(pl_world_is_linear(h, &mut out), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn step_to_event_stops_at_apex_then_ground() {
+    let _guard = serial();
+    let (mut t, mut kind) = (0.0, -1);
+    unsafe {
+        let h = pl_world_create(0.0, 5.0);
+        pl_world_set_gravity(h, 10.0);
+        assert_eq!(pl_world_step_to_event(h, 0.2, &mut t, &mut kind), OK);
+        assert_eq!(kind, EVENT_TIMEOUT);
+        assert!((t - 0.2).abs() < 1e-12);
+
+        assert_eq!(pl_world_step_to_event(h, 5.0, &mut t, &mut kind), OK);
+        assert_eq!(kind, EVENT_APEX);
+        assert!((t - 0.5).abs() < 2e-3, "apex at {t}");
+        let (_, _, vy) = state_of(h);
+        assert!(vy <= 0.0 && vy > -1e-9, "vy {vy}");
+
+        assert_eq!(pl_world_step_to_event(h, 5.0, &mut t, &mut kind), OK);
+        assert_eq!(kind, EVENT_GROUND);
+        assert!((t - 1.0).abs() < 2e-3, "ground at {t}");
+        let (_, y, _) = state_of(h);
+        assert!(y <= 0.0 && y > -1e-9, "y {y}");
+
+        // Already below ground and falling: nothing left to hit.
+        assert_eq!(pl_world_step_to_event(h, 0.5, &mut t, &mut kind), OK);
+        assert_eq!(kind, EVENT_TIMEOUT);
+
+        assert_eq!(pl_world_step_to_event(h, 0.0, &mut t, &mut kind), INVALID_ARGUMENT);
+        assert_eq!(pl_world_step_to_event(h, 1.0, std::ptr::null_mut(), &mut kind), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_step_to_event(h, 1.0, &mut t, &mut kind), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn probe_predicts_step_without_mutating() {
+    let _guard = serial();
+    let (mut t, mut y, mut vy) = (0.0, 0.0, 0.0);
+    unsafe {
+        let h = pl_world_create(10.0, 1.0);
+        pl_world_set_noise(h, 3, 0.5);
+        pl_world_set_measurement_noise(h, 0.1, 0.1, 4);
+        assert_eq!(pl_world_probe(h, 0.01, 50, &mut t, &mut y, &mut vy), OK);
+        let mut count = 1;
+        pl_world_step_count(h, &mut count);
+        assert_eq!(count, 0);
+        let mut truth = (0.0, 0.0, 0.0);
+        pl_world_get_true_state(h, &mut truth.0, &mut truth.1, &mut truth.2);
+        assert_eq!(truth, (0.0, 10.0, 1.0));
+
+        let probed = (t, y, vy);
+        assert_eq!(pl_world_step_get(h, 0.01, 50, &mut t, &mut y, &mut vy), OK);
+        assert_eq!((t, y, vy), probed);
+
+        let null = std::ptr::null_mut();
+        assert_eq!(pl_world_probe(h, 0.01, 1, &mut t, null, &mut vy), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_probe(h, 0.01, 1, &mut t, &mut y, &mut vy), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn check_stability_uses_table_stiffness() {
+    let _guard = serial();
+    let stable = |h: u64, dt: f64| {
+        let mut out = -1;
+        assert_eq!(unsafe { pl_world_check_stability(h, dt, &mut out) }, OK);
+        out
+    };
+    unsafe {
+        let h = pl_world_create(0.0, 0.0);
+        assert_eq!(stable(h, 100.0), 1);
+        // g = 100 * y about y = 0: omega = 10, so the limit is dt = 0.2.
+        let (alt, g) = ([-1.0, 1.0], [-100.0, 100.0]);
+        pl_world_set_gravity_table(h, alt.as_ptr(), g.as_ptr(), 2);
+        assert_eq!(stable(h, 0.19), 1);
+        assert_eq!(stable(h, 0.21), 0);
+        pl_world_set_time_scale(h, 0.5);
+        assert_eq!(stable(h, 0.21), 1);
+        let mut steps = 1;
+        pl_world_step_count(h, &mut steps);
+        assert_eq!(steps, 0);
+
+        let mut out = 0;
+        assert_eq!(pl_world_check_stability(h, -1.0, &mut out), INVALID_ARGUMENT);
+        assert_eq!(pl_world_check_stability(h, 0.1, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_check_stability(h, 0.1, &mut out), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn contact_epsilon_settles_a_spent_bounce() {
+    let _guard = serial();
+    unsafe {
+        let chatter = pl_world_create(1.0, 0.0);
+        let settled = pl_world_create(1.0, 0.0);
+        for h in [chatter, settled] {
+            pl_world_set_bounds(h, 0.0, 10.0, 0.3);
+        }
+        assert_eq!(pl_world_set_contact_epsilon(settled, 1e-3), OK);
+        pl_world_step(chatter, 0.01, 500);
+        pl_world_step(settled, 0.01, 500);
+        let (_, y, vy) = state_of(settled);
+        assert_eq!((y, vy), (0.0, 0.0));
+        // Without the epsilon the ball keeps hopping off the floor.
+        let (_, y, vy) = state_of(chatter);
+        assert!(y > 0.0 && vy != 0.0, "y {y} vy {vy}");
+
+        assert_eq!(pl_world_set_contact_epsilon(settled, -1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_contact_epsilon(settled, f64::NAN), INVALID_ARGUMENT);
+        pl_world_destroy(chatter);
+        pl_world_destroy(settled);
+        assert_eq!(pl_world_set_contact_epsilon(settled, 0.0), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn trajectory_to_file_writes_csv_rows() {
+    let _guard = serial();
+    let path = std::env::temp_dir().join(format!("physicslab_traj_{}.csv", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    unsafe {
+        let h = pl_world_create(10.0, 0.0);
+        let rc = pl_world_trajectory_to_file(h, 0.01, 100, path_str.as_ptr(), path_str.len() as u32);
+        assert_eq!(rc, OK);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 101);
+        assert_eq!(lines[0], "t,y,vy");
+        let mut count = 1;
+        pl_world_step_count(h, &mut count);
+        assert_eq!(count, 0);
+        pl_world_step(h, 0.01, 100);
+        let (t, y, vy) = state_of(h);
+        assert_eq!(lines[100], format!("{t},{y},{vy}"));
+
+        let missing = "/nonexistent-dir/out.csv";
+        let rc = pl_world_trajectory_to_file(h, 0.01, 1, missing.as_ptr(), missing.len() as u32);
+        assert_eq!(rc, IO_ERROR);
+        assert_eq!(pl_last_error_code(), IO_ERROR);
+        let bad = [0xffu8, 0xfe];
+        assert_eq!(pl_world_trajectory_to_file(h, 0.01, 1, bad.as_ptr(), 2), INVALID_ARGUMENT);
+        assert_eq!(pl_world_trajectory_to_file(h, 0.01, 1, std::ptr::null(), 0), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+    }
+}
+
+#[test]
+fn equals_compares_state_and_parameters() {
+    let _guard = serial();
+    let equal = |a: u64, b: u64, tol: f64| {
+        let mut out = -1;
+        assert_eq!(unsafe { pl_world_equals(a, b, tol, &mut out) }, OK);
+        out
+    };
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(5.0, 1.0);
+        pl_world_set_noise(a, 9, 0.2);
+        pl_world_set_bounds(a, 0.0, 10.0, 0.8);
+        pl_world_step(a, 0.01, 100);
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        pl_snapshot_all(buf.as_mut_ptr(), needed);
+        let b = pl_world_create(5.0, 1.0);
+        assert_eq!(equal(a, b, 1e-9), 0);
+        pl_world_destroy(b);
+
+        // Restore, then rebuild the same configuration under a second handle.
+        assert_eq!(pl_restore_all(buf.as_ptr(), buf.len() as u32), OK);
+        let b = pl_world_create(5.0, 1.0);
+        pl_world_set_noise(b, 9, 0.2);
+        pl_world_set_bounds(b, 0.0, 10.0, 0.8);
+        pl_world_step(b, 0.01, 100);
+        assert_eq!(equal(a, b, 0.0), 1);
+        assert_eq!(equal(a, a, 0.0), 1);
+
+        pl_world_set_gravity(b, 9.81 + 1e-6);
+        assert_eq!(equal(a, b, 1e-9), 0);
+        assert_eq!(equal(a, b, 1e-3), 1);
+        pl_world_step(b, 1e-9, 1);
+        assert_eq!(equal(a, b, 1e-3), 0);
+
+        let mut out = 0;
+        assert_eq!(pl_world_equals(a, b, -1.0, &mut out), INVALID_ARGUMENT);
+        assert_eq!(pl_world_equals(a, b, 0.0, std::ptr::null_mut()), INVALID_ARGUMENT);
+        assert_eq!(pl_world_equals(a, 999, 0.0, &mut out), INVALID_HANDLE);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn spring_link_couples_two_worlds() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(2.0, 0.0);
+        let b = pl_world_create(0.0, 0.0);
+        let idle = pl_world_create(5.0, 0.0);
+        for h in [a, b] {
+            pl_world_set_gravity(h, 0.0);
+        }
+        let link = pl_link_spring(a, b, 10.0, 1.0);
+        assert_ne!(link, 0);
+        assert_eq!(pl_world_set_time_limit(a, -1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_last_error_code(a), INVALID_ARGUMENT);
+        let mut min_sep = f64::MAX;
+        for _ in 0..100 {
+            assert_eq!(pl_step_linked(0.01, 1), OK);
+            let ((_, ya, _), (_, yb, _)) = (state_of(a), state_of(b));
+            assert!((ya + yb - 2.0).abs() < 1e-9);
+            min_sep = min_sep.min(ya - yb);
+        }
+        // Released stretched by 1, the pair swings to about 1 compressed.
+        assert!(min_sep < 0.1, "min separation {min_sep}");
+        assert_eq!(state_of(idle), (0.0, 5.0, 0.0));
+        assert_eq!(pl_world_last_error_code(a), OK);
+        for (h, stepped) in [(a, true), (b, true), (idle, false)] {
+            let mut ns = u64::MAX;
+            assert_eq!(pl_world_last_step_nanos(h, &mut ns), OK);
+            assert_eq!(ns > 0, stepped);
+        }
+
+        assert_eq!(pl_unlink(link), OK);
+        assert_eq!(pl_unlink(link), INVALID_HANDLE);
+        let before = state_of(a);
+        assert_eq!(pl_step_linked(0.01, 10), OK);
+        assert_eq!(state_of(a), before);
+
+        assert_eq!(pl_link_spring(a, a, 1.0, 0.0), 0);
+        assert_eq!(pl_last_error_code(), INVALID_ARGUMENT);
+        assert_eq!(pl_link_spring(a, b, -1.0, 0.0), 0);
+        assert_eq!(pl_link_spring(a, 999, 1.0, 0.0), 0);
+        assert_eq!(pl_last_error_code(), INVALID_HANDLE);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn spring_linked_worlds_conserve_total_momentum() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(3.0, 1.0);
+        let b = pl_world_create(0.0, -0.25);
+        for h in [a, b] {
+            pl_world_set_gravity(h, 0.0);
+        }
+        pl_world_add_body(a, 10.0, 0.5, 2.0, 0.1);
+        pl_link_spring(a, b, 25.0, 1.0);
+        let handles = [a, 999, b];
+        let mut initial = 0.0;
+        assert_eq!(pl_total_momentum(handles.as_ptr(), 3, &mut initial), OK);
+        assert_eq!(initial, 1.0 - 0.25 + 2.0 * 0.5);
+        for _ in 0..200 {
+            pl_step_linked(0.01, 1);
+            let mut p = 0.0;
+            pl_total_momentum(handles.as_ptr(), 3, &mut p);
+            assert!((p - initial).abs() < 1e-12, "momentum {p}");
+        }
+        // The spring did work: the pair's velocities changed.
+        assert_ne!(state_of(a).2, 1.0);
+
+        let mut p = 1.0;
+        assert_eq!(pl_total_momentum(std::ptr::null(), 0, &mut p), OK);
+        assert_eq!(p, 0.0);
+        assert_eq!(pl_total_momentum(handles.as_ptr(), 3, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn total_energy_sums_kinetic_and_potential() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let flat = pl_world_create(3.0, 4.0);
+        pl_world_set_gravity(flat, 2.0);
+        pl_world_add_body(flat, -1.0, 1.0, 2.0, 0.1);
+        // g rises linearly from 1 at the ground to 3 at y = 10.
+        let table = pl_world_create(10.0, 0.0);
+        let altitudes = [0.0, 10.0];
+        let gravities = [1.0, 3.0];
+        pl_world_set_gravity_table(table, altitudes.as_ptr(), gravities.as_ptr(), 2);
+        let handles = [flat, 999, table];
+        let mut total = 0.0;
+        assert_eq!(pl_total_energy(handles.as_ptr(), 3, &mut total), OK);
+        let flat_energy = 0.5 * 16.0 + 2.0 * 3.0 + 2.0 * (0.5 - 2.0);
+        assert!((total - (flat_energy + 20.0)).abs() < 1e-12, "energy {total}");
+
+        // A free fall trades potential for kinetic energy.
+        pl_world_step(table, 0.001, 1000);
+        let mut later = 0.0;
+        pl_total_energy(handles[2..].as_ptr(), 1, &mut later);
+        assert!((later - 20.0).abs() < 0.05, "energy {later}");
+
+        assert_eq!(pl_total_energy(std::ptr::null(), 0, &mut total), OK);
+        assert_eq!(total, 0.0);
+        assert_eq!(pl_total_energy(handles.as_ptr(), 3, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn restore_all_replaces_spring_links() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let a = pl_world_create(2.0, 0.0);
+        let b = pl_world_create(0.0, 0.0);
+        let c = pl_world_create(7.0, 0.0);
+        for h in [a, b, c] {
+            pl_world_set_gravity(h, 0.0);
+        }
+        let kept = pl_link_spring(a, b, 10.0, 1.0);
+        // Inert once c is gone, so it is left out of the archive.
+        let inert = pl_link_spring(b, c, 10.0, 0.0);
+        pl_world_destroy(c);
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        pl_snapshot_all(buf.as_mut_ptr(), needed);
+        pl_step_linked(0.01, 50);
+        let expected = (state_of(a), state_of(b));
+
+        // A link made after the snapshot must not couple the restored worlds.
+        pl_unlink(kept);
+        let stale = pl_link_spring(a, b, 50.0, 0.0);
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), OK);
+        assert_eq!(pl_unlink(stale), INVALID_HANDLE);
+        assert_eq!(pl_unlink(inert), INVALID_HANDLE);
+        pl_step_linked(0.01, 50);
+        assert_eq!((state_of(a), state_of(b)), expected);
+        assert!(pl_link_spring(a, b, 1.0, 0.0) > stale);
+        assert_eq!(pl_unlink(kept), OK);
+
+        // The archived link's second world is not in the archive.
+        let len = buf.len();
+        buf[len - 24..len - 16].copy_from_slice(&999u64.to_le_bytes());
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), INVALID_ARGUMENT);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn net_acceleration_evaluates_the_force_model() {
+    let _guard = serial();
+    let mut a = 0.0;
+    unsafe {
+        let h = pl_world_create(5.0, 0.0);
+        assert_eq!(pl_world_net_acceleration(h, &mut a), OK);
+        assert_eq!(a, -9.81);
+        let (alt, g) = ([0.0, 10.0], [10.0, 8.0]);
+        pl_world_set_gravity_table(h, alt.as_ptr(), g.as_ptr(), 2);
+        assert_eq!(pl_world_net_acceleration(h, &mut a), OK);
+        assert!((a + 9.0).abs() < 1e-12);
+
+        // Querying must not consume the noise stream.
+        let twin = pl_world_create(5.0, 0.0);
+        pl_world_set_gravity_table(twin, alt.as_ptr(), g.as_ptr(), 2);
+        for w in [h, twin] {
+            pl_world_set_noise(w, 11, 1.0);
+        }
+        pl_world_net_acceleration(h, &mut a);
+        pl_world_step(h, 0.01, 10);
+        pl_world_step(twin, 0.01, 10);
+        assert_eq!(state_of(h), state_of(twin));
+
+        // No drag model: the drag term is always zero.
+        let mut f = 1.0;
+        assert_eq!(pl_world_drag_force(h, &mut f), OK);
+        assert_eq!(f, 0.0);
+
+        assert_eq!(pl_world_net_acceleration(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        assert_eq!(pl_world_drag_force(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        pl_world_destroy(twin);
+        assert_eq!(pl_world_net_acceleration(h, &mut a), INVALID_HANDLE);
+        assert_eq!(pl_world_drag_force(h, &mut f), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn max_speed_records_impact_speed() {
+    let _guard = serial();
+    let mut v = 0.0;
+    unsafe {
+        let h = pl_world_create(20.0, -3.0);
+        assert_eq!(pl_world_max_speed(h, &mut v), OK);
+        assert_eq!(v, 3.0);
+        pl_world_set_bounds(h, 0.0, 100.0, 0.5);
+        pl_world_step(h, 1e-3, 5000);
+        // The peak is the speed just before the first bounce, never after.
+        let impact = (3.0f64 * 3.0 + 2.0 * 9.81 * 20.0).sqrt();
+        assert_eq!(pl_world_max_speed(h, &mut v), OK);
+        assert!((v - impact).abs() / impact < 1e-3, "peak {v} vs {impact}");
+        let (_, _, vy) = state_of(h);
+        assert!(vy.abs() < v);
+
+        assert_eq!(pl_world_max_speed(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_max_speed(h, &mut v), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn time_limit_truncates_the_final_step() {
+    let _guard = serial();
+    let (mut t, mut y, mut vy) = (0.0, 0.0, 0.0);
+    unsafe {
+        let h = pl_world_create(100.0, 0.0);
+        assert_eq!(pl_world_set_time_limit(h, 1.0), OK);
+        assert_eq!(pl_world_step(h, 0.25, 4), OK);
+        assert_eq!(pl_world_step(h, 0.3, 1), TIME_LIMIT_REACHED);
+        assert_eq!(pl_last_error_code(), TIME_LIMIT_REACHED);
+        let limited = state_of(h);
+        assert_eq!(limited.0, 1.0);
+        assert_eq!(pl_world_step_get(h, 0.1, 3, &mut t, &mut y, &mut vy), TIME_LIMIT_REACHED);
+        assert_eq!((t, y, vy), limited);
+
+        let g = pl_world_create(100.0, 0.0);
+        pl_world_step(g, 0.3, 3);
+        assert_eq!(pl_world_set_time_limit(g, 2.0), OK);
+        assert_eq!(pl_world_step(g, 0.3, 10), TIME_LIMIT_REACHED);
+        let mut count = 0;
+        pl_world_step_count(g, &mut count);
+        assert_eq!((state_of(g).0, count), (2.0, 7));
+
+        assert_eq!(pl_world_set_time_limit(h, 1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_time_limit(h, f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_time_limit(h, f64::INFINITY), OK);
+        assert_eq!(pl_world_step(h, 0.3, 1), OK);
+        pl_world_destroy(h);
+        pl_world_destroy(g);
+        assert_eq!(pl_world_set_time_limit(h, 5.0), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn step_unchecked_matches_step_and_skips_validation() {
+    let _guard = serial();
+    unsafe {
+        let checked = pl_world_create(10.0, 1.0);
+        let unchecked = pl_world_create(10.0, 1.0);
+        pl_world_step(checked, 0.01, 500);
+        assert_eq!(pl_world_step_unchecked(unchecked, 0.01, 500), OK);
+        assert_eq!(state_of(checked), state_of(unchecked));
+
+        assert_eq!(pl_world_step_unchecked(unchecked, 1e-6, 20_000), OK);
+        assert_eq!(pl_world_step_unchecked(unchecked, f64::NAN, 1), OK);
+        assert!(state_of(unchecked).1.is_nan());
+        pl_world_destroy(checked);
+        pl_world_destroy(unchecked);
+        assert_eq!(pl_world_step_unchecked(unchecked, 0.01, 1), INVALID_HANDLE);
+    }
+}
+
+// Hooke's law with k read from `user`.
+extern "C" fn spring_accel(_t: f64, y: f64, _vy: f64, user: *mut std::ffi::c_void) -> f64 {
+    let k = unsafe { *(user as *const f64) };
+    -k * y
+}
+
+extern "C" fn nan_accel(_t: f64, _y: f64, _vy: f64, _user: *mut std::ffi::c_void) -> f64 {
+    f64::NAN
+}
+
+#[test]
+fn step_custom_integrates_a_host_force() {
+    let _guard = serial();
+    let mut k = 4.0f64;
+    let user = &mut k as *mut f64 as *mut std::ffi::c_void;
+    unsafe {
+        let h = pl_world_create(1.0, 0.0);
+        // omega = 2, so a full period is pi seconds.
+        let steps = (std::f64::consts::PI / 1e-3).round() as u32;
+        assert_eq!(pl_world_step_custom(h, 1e-3, steps, Some(spring_accel), user), OK);
+        let (_, y, vy) = state_of(h);
+        assert!((y - 1.0).abs() < 1e-2 && vy.abs() < 1e-2, "y {y} vy {vy}");
+
+        let before = state_of(h);
+        assert_eq!(pl_world_step_custom(h, 0.01, 1, Some(nan_accel), user), INVALID_ARGUMENT);
+        assert_eq!(state_of(h), before);
+        assert_eq!(pl_world_step_custom(h, 0.01, 1, None, user), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_step_custom(h, 0.01, 1, Some(spring_accel), user), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn per_world_errors_are_attributed() {
+    let _guard = serial();
+    unsafe {
+        let ok = pl_world_create(0.0, 0.0);
+        let capped = pl_world_create(0.0, 0.0);
+        pl_world_set_time_limit(capped, 0.05);
+        assert_eq!(pl_world_step(capped, 0.1, 1), TIME_LIMIT_REACHED);
+        assert_eq!(pl_world_step(ok, 0.1, 1), OK);
+        assert_eq!(pl_last_error_code(), OK);
+        assert_eq!(pl_world_last_error_code(ok), OK);
+        assert_eq!(pl_world_last_error_code(capped), TIME_LIMIT_REACHED);
+        let mut msg = [0u8; 64];
+        let len = pl_world_last_error_message(capped, msg.as_mut_ptr(), msg.len() as u32) as usize;
+        assert_eq!(&msg[..len], b"world reached its time limit");
+        // Reading does not reset it; the next call on the world does.
+        assert_eq!(pl_world_last_error_code(capped), TIME_LIMIT_REACHED);
+        assert_eq!(pl_world_step_count(capped, &mut 0), OK);
+        assert_eq!(pl_world_last_error_code(capped), OK);
+        assert_eq!(pl_world_last_error_message(capped, msg.as_mut_ptr(), msg.len() as u32), 0);
+
+        pl_world_destroy(ok);
+        assert_eq!(pl_world_last_error_code(ok), INVALID_HANDLE);
+        pl_world_destroy(capped);
+    }
+}
+
+#[test]
+fn scheduled_gravity_changes_apply_in_time_order() {
+    let _guard = serial();
+    let gravity = |h: u64| {
+        let mut g = 0.0;
+        assert_eq!(unsafe { pl_world_get_gravity(h, &mut g) }, OK);
+        g
+    };
+    unsafe {
+        let h = pl_world_create(100.0, 0.0);
+        assert_eq!(pl_world_schedule_gravity(h, 0.45, 0.0), OK);
+        assert_eq!(pl_world_schedule_gravity(h, 0.25, 20.0), OK);
+        pl_world_step(h, 0.1, 2);
+        assert_eq!(gravity(h), 9.81);
+        pl_world_step(h, 0.1, 1);
+        assert_eq!(gravity(h), 20.0);
+        pl_world_step(h, 0.1, 2);
+        assert_eq!(gravity(h), 0.0);
+        // With g = 0 the particle coasts.
+        let (_, _, vy) = state_of(h);
+        pl_world_step(h, 0.1, 5);
+        assert_eq!(state_of(h).2, vy);
+
+        assert_eq!(pl_world_schedule_gravity(h, 0.5, 1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_last_error_code(h), INVALID_ARGUMENT);
+        assert_eq!(pl_world_schedule_gravity(h, 2.0, f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_world_schedule_gravity(h, f64::INFINITY, 1.0), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_schedule_gravity(h, 2.0, 1.0), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn seed_all_reproduces_the_registry() {
+    let _guard = serial();
+    unsafe { pl_reset_for_testing() };
+    // Each run builds a fresh registry on new handles, then tears it down.
+    let run = |master: u64| unsafe {
+        let quiet = pl_world_create(10.0, 0.0);
+        let noisy: Vec<u64> = (0..3).map(|_| pl_world_create(10.0, 0.0)).collect();
+        for &h in &noisy {
+            pl_world_set_noise(h, 0, 1.0);
+        }
+        assert_eq!(pl_seed_all(master), OK);
+        pl_world_step(quiet, 0.01, 100);
+        let states = noisy
+            .iter()
+            .map(|&h| {
+                pl_world_step(h, 0.01, 100);
+                state_of(h)
+            })
+            .collect::<Vec<_>>();
+        pl_world_destroy(quiet);
+        for h in noisy {
+            pl_world_destroy(h);
+        }
+        states
+    };
+    let first = run(42);
+    assert_eq!(run(42), first);
+    assert_ne!(run(43), first);
+    assert_ne!(first[0], first[1]);
+    unsafe { pl_reset_for_testing() };
+}
+
+#[test]
+fn integrator_order_matches_convergence() {
+    let _guard = serial();
+    let mut order = 0;
+    unsafe {
+        let h = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_integrator_order(h, &mut order), OK);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_integrator_order(h, &mut order), INVALID_HANDLE);
+        assert_eq!(pl_world_integrator_order(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+    }
+    assert_eq!(order, 1);
+    // Constant gravity from rest: exact y(1) = -g / 2.
+    let exact = -9.81 / 2.0;
+    let err = |steps: u32| (run_sim(0.0, 0.0, 1.0 / f64::from(steps), steps).1 - exact).abs();
+    let ratio = err(100) / err(200);
+    assert!((ratio - 2.0).abs() < 0.05, "ratio {ratio}");
+}
+
+#[test]
+fn boundary_modes_reflect_clamp_and_wrap() {
+    let _guard = serial();
+    unsafe {
+        // From rest at y = 1 with g = 2 the floor is reached at t = 1.
+        let worlds: Vec<u64> = (0..3).map(|_| pl_world_create(1.0, 0.0)).collect();
+        for (mode, &h) in worlds.iter().enumerate() {
+            pl_world_set_gravity(h, 2.0);
+            pl_world_set_bounds(h, 0.0, 4.0, 1.0);
+            assert_eq!(pl_world_set_boundary_mode(h, mode as i32), OK);
+            pl_world_step(h, 0.01, 110);
+        }
+        let (_, y, vy) = state_of(worlds[0]);
+        assert!(y > 0.0 && vy > 0.0, "reflect: y {y} vy {vy}");
+        assert_eq!(state_of(worlds[1]).1, 0.0);
+        assert!(state_of(worlds[1]).2.abs() <= 0.02);
+        let (_, y, vy) = state_of(worlds[2]);
+        assert!(y > 3.0 && y < 4.0 && vy < -2.0, "wrap: y {y} vy {vy}");
+
+        let h = worlds[0];
+        let b = pl_world_add_body(h, 3.9, 5.0, 1.0, 0.1);
+        pl_world_set_boundary_mode(h, 1);
+        pl_world_step(h, 0.01, 1);
+        let (by, bvy) = body_state(h, b);
+        assert_eq!((by, bvy), (3.9, 0.0));
+
+        assert_eq!(pl_world_set_boundary_mode(h, 3), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_boundary_mode(h, -1), INVALID_ARGUMENT);
+        for h in worlds {
+            pl_world_destroy(h);
+        }
+        assert_eq!(pl_world_set_boundary_mode(h, 0), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn trajectory_stats_summarise_a_copy() {
+    let _guard = serial();
+    let (mut min_y, mut max_y, mut mean_y, mut final_vy) = (0.0, 0.0, 0.0, 0.0);
+    unsafe {
+        let h = pl_world_create(10.0, 5.0);
+        let rc = pl_world_trajectory_stats(h, 0.01, 200, &mut min_y, &mut max_y, &mut mean_y, &mut final_vy);
+        assert_eq!(rc, OK);
+        assert_eq!(state_of(h), (0.0, 10.0, 5.0));
+
+        let (mut lo, mut hi, mut sum) = (f64::MAX, f64::MIN, 0.0);
+        for _ in 0..200 {
+            pl_world_step(h, 0.01, 1);
+            let (_, y, _) = state_of(h);
+            lo = lo.min(y);
+            hi = hi.max(y);
+            sum += y;
+        }
+        assert_eq!((min_y, max_y, final_vy), (lo, hi, state_of(h).2));
+        assert!((mean_y - sum / 200.0).abs() < 1e-12);
+        assert!(max_y > 11.0);
+
+        let null = std::ptr::null_mut();
+        let rc = pl_world_trajectory_stats(h, 0.01, 1, &mut min_y, null, &mut mean_y, &mut final_vy);
+        assert_eq!(rc, INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        let rc = pl_world_trajectory_stats(h, 0.01, 1, &mut min_y, &mut max_y, &mut mean_y, &mut final_vy);
+        assert_eq!(rc, INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn max_worlds_refuses_creation_cleanly() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        assert_eq!(pl_get_max_worlds(), 0);
+        assert_eq!(pl_set_max_worlds(2), OK);
+        let a = pl_world_create(0.0, 0.0);
+        let b = pl_world_create(0.0, 0.0);
+        assert!(a != 0 && b != 0);
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut two = vec![0u8; needed as usize];
+        pl_snapshot_all(two.as_mut_ptr(), needed);
+
+        assert_eq!(pl_world_create(0.0, 0.0), 0);
+        assert_eq!(pl_last_error_code(), POLICY_DENIED);
+        assert_eq!(pl_last_error_detail(), DETAIL_WORLD_LIMIT);
+        assert_eq!(pl_world_count(), 2);
+        pl_world_destroy(a);
+        assert_ne!(pl_world_create(0.0, 0.0), 0);
+
+        assert_eq!(pl_restore_all(two.as_ptr(), two.len() as u32), OK);
+        pl_set_max_worlds(1);
+        assert_eq!(pl_restore_all(two.as_ptr(), two.len() as u32), POLICY_DENIED);
+        assert_eq!(pl_world_count(), 2);
+        pl_reset_for_testing();
+        assert_eq!(pl_get_max_worlds(), 0);
+    }
+}
+
+#[test]
+fn airborne_time_restarts_at_floor_contact() {
+    let _guard = serial();
+    let mut airborne = -1.0;
+    unsafe {
+        let h = pl_world_create(5.0, 0.0);
+        pl_world_set_bounds(h, 0.0, 100.0, 1.0);
+        pl_world_step(h, 1e-3, 500);
+        // Still falling: airborne for the whole run.
+        assert_eq!(pl_world_airborne_time(h, &mut airborne), OK);
+        assert_eq!(airborne, state_of(h).0);
+
+        // First landing after sqrt(2 * 5 / 9.81) s, then 0.2 s on the way up.
+        let landing = (2.0f64 * 5.0 / 9.81).sqrt();
+        pl_world_step(h, 1e-3, 700);
+        let (t, _, vy) = state_of(h);
+        assert!(vy > 0.0);
+        assert_eq!(pl_world_airborne_time(h, &mut airborne), OK);
+        assert!((t - airborne - landing).abs() < 2e-3, "contact at {}", t - airborne);
+
+        // Bounds that clamp the particle onto the floor land it there.
+        let below = pl_world_create(0.0, 0.0);
+        pl_world_step(below, 1e-3, 300);
+        pl_world_set_bounds(below, 0.0, 10.0, 1.0);
+        assert_eq!(pl_world_airborne_time(below, &mut airborne), OK);
+        assert_eq!(airborne, 0.0);
+
+        assert_eq!(pl_world_airborne_time(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        assert_eq!(pl_world_airborne_time(below + 1, &mut airborne), INVALID_HANDLE);
+        pl_world_destroy(h);
+        pl_world_destroy(below);
+    }
+}
+
+#[test]
+fn energy_dissipated_balances_the_bounce() {
+    let _guard = serial();
+    unsafe {
+        let inelastic = pl_world_create(1.0, 0.0);
+        pl_world_set_bounds(inelastic, 0.0, 10.0, 0.5);
+        let elastic = pl_world_create(1.0, 0.0);
+        pl_world_set_bounds(elastic, 0.0, 10.0, 1.0);
+        let mut initial = 0.0;
+        pl_total_energy(&inelastic, 1, &mut initial);
+        let mut dissipated = -1.0;
+        assert_eq!(pl_world_energy_dissipated(inelastic, &mut dissipated), OK);
+        assert_eq!(dissipated, 0.0);
+
+        // One bounce at e = 0.5 keeps a quarter of the impact energy.
+        for h in [inelastic, elastic] {
+            pl_world_step(h, 1e-4, 5000);
+        }
+        assert!(state_of(inelastic).2 > 0.0);
+        let mut remaining = 0.0;
+        pl_total_energy(&inelastic, 1, &mut remaining);
+        pl_world_energy_dissipated(inelastic, &mut dissipated);
+        assert!((dissipated - 0.75 * initial).abs() < 1e-2, "dissipated {dissipated}");
+        assert!((remaining + dissipated - initial).abs() < 1e-2, "{remaining} + {dissipated}");
+        assert_eq!(pl_world_energy_dissipated(elastic, &mut dissipated), OK);
+        assert_eq!(dissipated, 0.0);
+
+        assert_eq!(pl_world_energy_dissipated(elastic, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(elastic);
+        assert_eq!(pl_world_energy_dissipated(elastic, &mut dissipated), INVALID_HANDLE);
+        pl_world_destroy(inelastic);
+    }
+}
+
+#[test]
+fn divergence_flag_is_sticky() {
+    let _guard = serial();
+    let mut diverged = -1;
+    unsafe {
+        let clean = pl_world_create(10.0, 0.0);
+        pl_world_step(clean, 1e-3, 1000);
+        assert_eq!(pl_world_has_diverged(clean, &mut diverged), OK);
+        assert_eq!(diverged, 0);
+
+        let h = pl_world_create(10.0, 0.0);
+        assert_eq!(pl_world_step_unchecked(h, f64::INFINITY, 1), OK);
+        assert_eq!(pl_world_has_diverged(h, &mut diverged), OK);
+        assert_eq!(diverged, 1);
+        // Later successful calls clear the error state but not the flag.
+        assert_eq!(pl_world_set_gravity(h, 1.0), OK);
+        assert_eq!(pl_world_last_error_code(h), OK);
+        assert_eq!(pl_world_has_diverged(h, &mut diverged), OK);
+        assert_eq!(diverged, 1);
+
+        assert_eq!(pl_world_has_diverged(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(clean);
+        pl_world_destroy(h);
+    }
+}
+
+#[test]
+fn regions_add_acceleration_inside_their_band() {
+    let _guard = serial();
+    let mut a = 0.0;
+    unsafe {
+        let h = pl_world_create(5.0, 0.0);
+        pl_world_set_gravity(h, 10.0);
+        assert_eq!(pl_world_add_region(h, 0.0, 10.0, 4.0), OK);
+        assert_eq!(pl_world_add_region(h, 4.0, 6.0, 1.0), OK);
+        assert_eq!(pl_world_add_region(h, 6.0, 8.0, 100.0), OK);
+        assert_eq!(pl_world_net_acceleration(h, &mut a), OK);
+        assert_eq!(a, -5.0);
+        let mut linear = -1;
+        pl_world_is_linear(h, &mut linear);
+        assert_eq!(linear, 0);
+
+        // Inside both lower bands for the whole run: falls at 5 (semi-implicit
+        // Euler, so y drops by a * dt^2 * (1 + ... + n)).
+        pl_world_step(h, 0.01, 10);
+        let (_, y, vy) = state_of(h);
+        assert!((vy + 0.5).abs() < 1e-12, "vy {vy}");
+        assert!((y - (5.0 - 5.0 * 0.01 * 0.01 * 55.0)).abs() < 1e-12, "y {y}");
+
+        assert_eq!(pl_world_clear_regions(h), OK);
+        assert_eq!(pl_world_net_acceleration(h, &mut a), OK);
+        assert_eq!(a, -10.0);
+
+        assert_eq!(pl_world_add_region(h, 2.0, 2.0, 1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_add_region(h, 0.0, f64::INFINITY, 1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_add_region(h, 0.0, 1.0, f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_world_clear_regions(h + 1), INVALID_HANDLE);
+        pl_world_destroy(h);
+    }
+}
+
+#[test]
+fn dominant_force_picks_the_largest_term() {
+    let _guard = serial();
+    let dominant = |h: u64| {
+        let (mut kind, mut magnitude) = (-1, -1.0);
+        assert_eq!(unsafe { pl_world_dominant_force(h, &mut kind, &mut magnitude) }, OK);
+        (kind, magnitude)
+    };
+    unsafe {
+        let h = pl_world_create(5.0, 0.0);
+        pl_world_set_gravity(h, 10.0);
+        assert_eq!(dominant(h), (FORCE_GRAVITY, 10.0));
+        // Regions overlapping y sum into one term.
+        pl_world_add_region(h, 0.0, 10.0, -8.0);
+        pl_world_add_region(h, 4.0, 6.0, -4.0);
+        assert_eq!(dominant(h), (FORCE_REGIONS, 12.0));
+        // Displaces three times its unit mass: buoyancy 3g.
+        pl_world_set_buoyancy(h, 1000.0, 0.003, 20.0);
+        assert_eq!(dominant(h), (FORCE_BUOYANCY, 30.0));
+
+        let still = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(still, 0.0);
+        assert_eq!(dominant(still), (FORCE_GRAVITY, 0.0));
+
+        let mut kind = 0;
+        assert_eq!(pl_world_dominant_force(h, &mut kind, std::ptr::null_mut()), INVALID_ARGUMENT);
+        let mut magnitude = 0.0;
+        assert_eq!(pl_world_dominant_force(still + 1, &mut kind, &mut magnitude), INVALID_HANDLE);
+        pl_world_destroy(h);
+        pl_world_destroy(still);
+    }
+}
+
+#[test]
+fn buoyancy_floats_a_light_particle_at_the_surface() {
+    let _guard = serial();
+    let mut a = 0.0;
+    unsafe {
+        // Displaces twice its unit mass: buoyancy 2g below the surface.
+        let float = pl_world_create(10.0, 0.0);
+        pl_world_set_gravity(float, 10.0);
+        assert_eq!(pl_world_set_buoyancy(float, 1000.0, 0.002, 10.0), OK);
+        assert_eq!(pl_world_net_acceleration(float, &mut a), OK);
+        assert_eq!(a, -10.0);
+        let mut linear = -1;
+        pl_world_is_linear(float, &mut linear);
+        assert_eq!(linear, 0);
+        let mut worst: f64 = 0.0;
+        for _ in 0..5000 {
+            pl_world_step(float, 0.001, 1);
+            worst = worst.max((state_of(float).1 - 10.0).abs());
+        }
+        assert!(worst < 1e-4, "drifted {worst} from the surface");
+
+        // Displaces half its mass: net g/2 downward below the surface.
+        let sink = pl_world_create(5.0, 0.0);
+        pl_world_set_gravity(sink, 10.0);
+        pl_world_set_buoyancy(sink, 1000.0, 0.0005, 10.0);
+        pl_world_net_acceleration(sink, &mut a);
+        assert_eq!(a, -5.0);
+        pl_world_step(sink, 0.01, 100);
+        let (_, y, vy) = state_of(sink);
+        assert!(y < 5.0 && vy < 0.0);
+
+        assert_eq!(pl_world_set_buoyancy(sink, 0.0, 1.0, 0.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_buoyancy(sink, 1.0, -1.0, 0.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_buoyancy(sink, 1.0, 1.0, f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_buoyancy(0, 1.0, 1.0, 0.0), INVALID_HANDLE);
+        pl_world_destroy(float);
+        pl_world_destroy(sink);
+    }
+}
+
+#[test]
+fn total_steps_counts_every_world() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        assert_eq!(pl_total_steps_executed(), 0);
+        let a = pl_world_create(10.0, 0.0);
+        let b = pl_world_create(10.0, 0.0);
+        pl_world_step(a, 1e-3, 100);
+        pl_world_step(b, 1e-3, 50);
+        assert_eq!(pl_total_steps_executed(), 150);
+        assert_eq!(pl_world_step(a, 1e-3, 10_001), POLICY_DENIED);
+        assert_eq!(pl_world_step(a, f64::NAN, 10), INVALID_ARGUMENT);
+        assert_eq!(pl_total_steps_executed(), 150);
+        let (mut t, mut y, mut vy) = (0.0, 0.0, 0.0);
+        pl_world_probe(a, 1e-3, 25, &mut t, &mut y, &mut vy);
+        assert_eq!(pl_total_steps_executed(), 175);
+        pl_reset_for_testing();
+        assert_eq!(pl_total_steps_executed(), 0);
+    }
+}
+
+#[test]
+fn coinciding_events_report_ground_first() {
+    let _guard = serial();
+    // Barely above the ground and barely rising: the shortest step that
+    // passes the apex also ends below the ground.
+    let run = || {
+        let (mut t, mut kind) = (0.0, -1);
+        unsafe {
+            let h = pl_world_create(1e-30, 1e-8);
+            pl_world_set_gravity(h, 10.0);
+            assert_eq!(pl_world_step_to_event(h, 1.0, &mut t, &mut kind), OK);
+            let (_, y, vy) = state_of(h);
+            pl_world_destroy(h);
+            (kind, t.to_bits(), y.to_bits(), vy.to_bits())
+        }
+    };
+    let first = run();
+    let (kind, _, y, vy) = first;
+    assert_eq!(kind, EVENT_GROUND);
+    assert!(f64::from_bits(y) <= 0.0 && f64::from_bits(vy) <= 0.0);
+    for _ in 0..3 {
+        assert_eq!(run(), first);
+    }
+}
+
+#[test]
+fn get_ground_reports_floor_bound() {
+    let _guard = serial();
+    let (mut ground, mut e, mut enabled) = (-1.0, -1.0, -1);
+    unsafe {
+        let h = pl_world_create(5.0, 0.0);
+        assert_eq!(pl_world_get_ground(h, &mut ground, &mut e, &mut enabled), OK);
+        assert_eq!((ground, e, enabled), (0.0, 0.0, 0));
+
+        pl_world_set_bounds(h, -2.0, 50.0, 0.75);
+        assert_eq!(pl_world_get_ground(h, &mut ground, &mut e, &mut enabled), OK);
+        assert_eq!((ground, e, enabled), (-2.0, 0.75, 1));
+        let mut only_y = 0.0;
+        assert_eq!(pl_world_get_ground(h, &mut only_y, std::ptr::null_mut(), std::ptr::null_mut()), OK);
+        assert_eq!(only_y, -2.0);
+
+        let null = std::ptr::null_mut();
+        assert_eq!(pl_world_get_ground(h, null, null, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        ground = 9.0;
+        assert_eq!(pl_world_get_ground(h, &mut ground, &mut e, &mut enabled), INVALID_HANDLE);
+        assert_eq!(ground, 9.0);
+    }
+}
+
+#[test]
+fn step_to_rest_settles_damped_bounce() {
+    let _guard = serial();
+    let mut steps = 0;
+    unsafe {
+        let h = pl_world_create(1.0, 0.0);
+        pl_world_set_bounds(h, 0.0, 10.0, 0.5);
+        pl_world_set_contact_epsilon(h, 1e-3);
+        assert_eq!(pl_world_step_to_rest(h, 1e-3, 1e-6, 1e-6, &mut steps), OK);
+        assert!(steps > 0 && steps < 10_000, "steps {steps}");
+        let mut count = 0;
+        pl_world_step_count(h, &mut count);
+        assert_eq!(count, u64::from(steps));
+        assert_eq!(state_of(h).1, 0.0);
+        // Already at rest: nothing to do.
+        assert_eq!(pl_world_step_to_rest(h, 1e-3, 1e-6, 1e-6, &mut steps), OK);
+        assert_eq!(steps, 0);
+
+        let falling = pl_world_create(1.0, 0.0);
+        assert_eq!(pl_world_step_to_rest(falling, 1e-3, 1e-6, 1e-6, &mut steps), POLICY_DENIED);
+        assert_eq!(pl_last_error_detail(), DETAIL_STEP_LIMIT);
+        assert_eq!(steps, 10_000);
+
+        assert_eq!(pl_world_step_to_rest(h, 1e-3, 0.0, 1e-6, &mut steps), INVALID_ARGUMENT);
+        assert_eq!(pl_world_step_to_rest(h, 1e-3, 1e-6, f64::NAN, &mut steps), INVALID_ARGUMENT);
+        assert_eq!(pl_world_step_to_rest(h, 1e-3, 1e-6, 1e-6, std::ptr::null_mut()), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        pl_world_destroy(falling);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn step_trace_reports_kick_and_drift() {
+    let _guard = serial();
+    let (mut y, mut vy) = (0.0, 0.0);
+    unsafe {
+        let h = pl_world_create(10.0, 0.0);
+        assert_eq!(pl_world_step_trace(h, 0.1, &mut y, &mut vy), OK);
+        let kicked = 0.0 - 9.81 * 0.1;
+        assert_eq!(vy, kicked);
+        assert_eq!(y, 10.0 + kicked * 0.1);
+        assert_eq!(state_of(h), (0.1, y, vy));
+        let mut ns = 0;
+        pl_world_last_step_nanos(h, &mut ns);
+        assert!(ns > 0);
+
+        // The drift overshoots the floor; the bounce comes after the trace.
+        let ball = pl_world_create(0.01, -1.0);
+        pl_world_set_bounds(ball, 0.0, 10.0, 1.0);
+        assert_eq!(pl_world_step_trace(ball, 0.1, &mut y, &mut vy), OK);
+        assert!(y < 0.0 && vy < 0.0);
+        let (_, y_after, vy_after) = state_of(ball);
+        assert_eq!((y_after, vy_after), (-y, -vy));
+
+        // Rollback undoes a traced step that overflows.
+        let crushed = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(crushed, 1e308);
+        pl_world_set_rollback(crushed, 1);
+        assert_eq!(pl_world_step_trace(crushed, 1.0, &mut y, &mut vy), OK);
+        assert_eq!(pl_world_step_trace(crushed, 1.0, &mut y, &mut vy), NUMERICAL_OVERFLOW);
+        assert_eq!(state_of(crushed), (1.0, -1e308, -1e308));
+        pl_world_destroy(crushed);
+
+        pl_world_set_time_limit(h, 0.15);
+        assert_eq!(pl_world_step_trace(h, 0.1, &mut y, &mut vy), TIME_LIMIT_REACHED);
+        assert_eq!(state_of(h).0, 0.1);
+        assert_eq!(pl_world_step_trace(h, 0.0, &mut y, &mut vy), INVALID_ARGUMENT);
+        assert_eq!(pl_world_step_trace(h, 0.01, std::ptr::null_mut(), &mut vy), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        pl_world_destroy(ball);
+    }
+}
+
+#[test]
+fn time_offset_shifts_reported_time_only() {
+    let _guard = serial();
+    unsafe {
+        let plain = pl_world_create(10.0, 1.0);
+        let shifted = pl_world_create(10.0, 1.0);
+        assert_eq!(pl_world_set_time_offset(shifted, 100.0), OK);
+        assert_eq!(state_of(shifted).0, 100.0);
+        // The limit is on the raw clock, far below the reported 100.5.
+        pl_world_set_time_limit(shifted, 0.6);
+        pl_world_step(plain, 0.01, 50);
+        assert_eq!(pl_world_step(shifted, 0.01, 50), OK);
+        let (t, y, vy) = state_of(plain);
+        assert_eq!(state_of(shifted), (t + 100.0, y, vy));
+        let mut count = 0;
+        pl_world_step_count(shifted, &mut count);
+        assert_eq!(count, 50);
+        let mut buf = [0u8; 64];
+        let needed = pl_world_get_state_proto(shifted, buf.as_mut_ptr(), buf.len() as u32);
+        assert!(needed as usize <= buf.len());
+        assert_eq!(buf[0], 0x09);
+        let proto_t = f64::from_le_bytes(buf[1..9].try_into().unwrap());
+        assert_eq!(proto_t, t + 100.0);
+
+        assert_eq!(pl_world_set_time_offset(shifted, f64::NAN), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_time_offset(0, 1.0), INVALID_HANDLE);
+        pl_world_destroy(plain);
+        pl_world_destroy(shifted);
+    }
+}
+
+#[derive(Default)]
+struct Streamed {
+    chunk_lens: Vec<u32>,
+    rows: Vec<(f64, f64, f64)>,
+}
+
+extern "C" fn collect_rows(
+    ts: *const f64,
+    ys: *const f64,
+    vys: *const f64,
+    n: u32,
+    user: *mut std::ffi::c_void,
+) {
+    let out = unsafe { &mut *(user as *mut Streamed) };
+    let n = n as usize;
+    let (ts, ys, vys) = unsafe {
+        (
+            std::slice::from_raw_parts(ts, n),
+            std::slice::from_raw_parts(ys, n),
+            std::slice::from_raw_parts(vys, n),
+        )
+    };
+    out.chunk_lens.push(n as u32);
+    out.rows.extend((0..n).map(|i| (ts[i], ys[i], vys[i])));
+    // No kernel lock is held, so re-entering is safe.
+    assert!(unsafe { pl_world_count() } > 0);
+}
+
+#[test]
+fn stream_delivers_batched_rows() {
+    let _guard = serial();
+    let mut streamed = Streamed::default();
+    unsafe {
+        let h = pl_world_create(10.0, 0.0);
+        let user = &mut streamed as *mut Streamed as *mut std::ffi::c_void;
+        assert_eq!(pl_world_stream(h, 0.01, 10, 4, Some(collect_rows), user), OK);
+        assert_eq!(streamed.chunk_lens, [4, 4, 2]);
+        assert_eq!(state_of(h).0, 0.0);
+        let mut expected = Vec::new();
+        for _ in 0..10 {
+            pl_world_step(h, 0.01, 1);
+            expected.push(state_of(h));
+        }
+        assert_eq!(streamed.rows, expected);
+
+        assert_eq!(pl_world_stream(h, 0.01, 10, 0, Some(collect_rows), user), INVALID_ARGUMENT);
+        assert_eq!(pl_world_stream(h, 0.01, 10, 4, None, user), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        assert_eq!(pl_world_stream(h, 0.01, 10, 4, Some(collect_rows), user), INVALID_HANDLE);
+    }
+}
+
+#[test]
+fn filter_selects_worlds_by_predicate() {
+    let _guard = serial();
+    let filter = |predicate: i32| {
+        let mut out = [0u64; 8];
+        let n = unsafe { pl_world_filter(predicate, out.as_mut_ptr(), out.len() as u32) };
+        out[..n as usize].to_vec()
+    };
+    unsafe {
+        pl_reset_for_testing();
+        let settled = pl_world_create(1.0, 0.0);
+        pl_world_set_bounds(settled, 0.0, 10.0, 0.5);
+        pl_world_set_contact_epsilon(settled, 1e-3);
+        let mut steps = 0;
+        assert_eq!(pl_world_step_to_rest(settled, 1e-3, 1e-6, 1e-6, &mut steps), OK);
+        let rising = pl_world_create(0.0, 5.0);
+        let diverged = pl_world_create(1.0, 0.0);
+        pl_world_step_unchecked(diverged, f64::INFINITY, 1);
+        let falling = pl_world_create(10.0, 0.0);
+        pl_world_step(falling, 1e-3, 10);
+        let rising_too = pl_world_create(0.0, 1.0);
+        // Bounced once and is falling again, well above the floor.
+        let bounced = pl_world_create(1.0, 0.0);
+        pl_world_set_bounds(bounced, 0.0, 10.0, 1.0);
+        pl_world_step(bounced, 1e-3, 1000);
+        let mut airborne = 0.0;
+        pl_world_airborne_time(bounced, &mut airborne);
+        assert!(airborne < 1.0);
+        assert!(state_of(bounced).1 > 0.5);
+        // Placed at rest on the floor by its bounds.
+        let clamped = pl_world_create(-1.0, 0.0);
+        pl_world_set_bounds(clamped, 0.0, 10.0, 0.5);
+
+        assert_eq!(filter(FILTER_LANDED), [settled, clamped]);
+        assert_eq!(filter(FILTER_ASLEEP), [settled, clamped]);
+        assert_eq!(filter(FILTER_DIVERGED), [diverged]);
+        assert_eq!(filter(FILTER_RISING), [rising, rising_too]);
+        assert_eq!(pl_world_filter(FILTER_RISING, std::ptr::null_mut(), 0), 2);
+
+        assert_eq!(pl_world_filter(9, std::ptr::null_mut(), 0), 0);
+        assert_eq!(pl_last_error_code(), INVALID_ARGUMENT);
+        pl_reset_for_testing();
+    }
+}
+
+extern "C" fn below_threshold(_t: f64, y: f64, _vy: f64, user: *mut std::ffi::c_void) -> i32 {
+    let threshold = unsafe { *(user as *const f64) };
+    i32::from(y < threshold)
+}
+
+#[test]
+fn step_until_stops_when_condition_holds() {
+    let _guard = serial();
+    let mut steps = 0;
+    unsafe {
+        let h = pl_world_create(10.0, 0.0);
+        let mut half = 5.0f64;
+        let user = &mut half as *mut f64 as *mut std::ffi::c_void;
+        assert_eq!(pl_world_step_until(h, 1e-3, 10_000, Some(below_threshold), user, &mut steps), OK);
+        let (_, y, _) = state_of(h);
+        assert!(y < 5.0);
+        // One step fewer from the start is still above the threshold.
+        let reference = pl_world_create(10.0, 0.0);
+        pl_world_step(reference, 1e-3, steps - 1);
+        assert!(state_of(reference).1 >= 5.0);
+
+        let mut never = -1.0f64;
+        let user = &mut never as *mut f64 as *mut std::ffi::c_void;
+        assert_eq!(pl_world_step_until(h, 1e-3, 100, Some(below_threshold), user, &mut steps), OK);
+        assert_eq!(steps, 100);
+
+        let rc = pl_world_step_until(h, 1e-3, 10_001, Some(below_threshold), user, &mut steps);
+        assert_eq!(rc, POLICY_DENIED);
+        assert_eq!(pl_world_step_until(h, 1e-3, 10, None, user, &mut steps), INVALID_ARGUMENT);
+        let null = std::ptr::null_mut();
+        assert_eq!(pl_world_step_until(h, 1e-3, 10, Some(below_threshold), user, null), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+        pl_world_destroy(reference);
+    }
+}
+
+#[test]
+fn world_age_survives_restore() {
+    let _guard = serial();
+    let mut age = -1.0;
+    unsafe {
+        pl_reset_for_testing();
+        let h = pl_world_create(1.0, 0.0);
+        assert_eq!(pl_world_age_seconds(h, &mut age), OK);
+        assert!((0.0..1.0).contains(&age), "age {age}");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        pl_world_step(h, 1.0, 100);
+        assert_eq!(pl_world_age_seconds(h, &mut age), OK);
+        assert!(age >= 0.02, "age {age}");
+
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        pl_snapshot_all(buf.as_mut_ptr(), needed);
+        pl_reset_for_testing();
+        assert_eq!(pl_restore_all(buf.as_ptr(), buf.len() as u32), OK);
+        let mut restored_age = 0.0;
+        assert_eq!(pl_world_age_seconds(h, &mut restored_age), OK);
+        assert!(restored_age >= age);
+
+        assert_eq!(pl_world_age_seconds(h, std::ptr::null_mut()), INVALID_ARGUMENT);
+        assert_eq!(pl_world_age_seconds(h + 1, &mut age), INVALID_HANDLE);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn origin_traces_back_to_launch_or_apex() {
+    let _guard = serial();
+    let (mut y, mut vy, mut t) = (0.0, 0.0, 0.0);
+    unsafe {
+        let h = pl_world_create(0.0, 10.0);
+        pl_world_step(h, 1e-4, 5000);
+        // Still rising: launched from the ground at t = 0.
+        assert_eq!(pl_world_origin(h, &mut y, &mut vy, &mut t), OK);
+        assert!(y.abs() < 1e-9, "y {y}");
+        assert!((vy - 10.0).abs() < 1e-2, "vy {vy}");
+        assert!(t.abs() < 1e-3, "t {t}");
+
+        // Past the apex: it fell from rest at the top.
+        pl_world_step(h, 1e-4, 10_000);
+        assert_eq!(pl_world_origin(h, &mut y, &mut vy, &mut t), OK);
+        assert!((y - 100.0 / (2.0 * 9.81)).abs() < 1e-2, "y {y}");
+        assert!(vy.abs() < 1e-12, "vy {vy}");
+        assert!((t - 10.0 / 9.81).abs() < 1e-3, "t {t}");
+        // The launch time follows the reported timeline.
+        let raw_t = t;
+        pl_world_set_time_offset(h, 100.0);
+        assert_eq!(pl_world_origin(h, &mut y, &mut vy, &mut t), OK);
+        assert_eq!(t, raw_t + 100.0);
+
+        pl_world_set_noise(h, 1, 0.1);
+        assert_eq!(pl_world_origin(h, &mut y, &mut vy, &mut t), POLICY_DENIED);
+        assert_eq!(pl_world_origin(h, &mut y, std::ptr::null_mut(), &mut t), INVALID_ARGUMENT);
+        pl_world_destroy(h);
+    }
+}
+
+#[test]
+fn rollback_undoes_overflowing_step() {
+    let _guard = serial();
+    unsafe {
+        // One step of this reaches -1e308; the second overflows to -inf.
+        let h = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(h, 1e308);
+        assert_eq!(pl_world_set_rollback(h, 1), OK);
+        assert_eq!(pl_world_step(h, 1.0, 5), NUMERICAL_OVERFLOW);
+        assert_eq!(pl_world_last_error_code(h), NUMERICAL_OVERFLOW);
+        assert_eq!(state_of(h), (1.0, -1e308, -1e308));
+        let mut count = 0;
+        pl_world_step_count(h, &mut count);
+        assert_eq!(count, 1);
+        let mut diverged = 0;
+        pl_world_has_diverged(h, &mut diverged);
+        assert_eq!(diverged, 1);
+
+        let plain = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(plain, 1e308);
+        assert_eq!(pl_world_step(plain, 1.0, 5), OK);
+        assert!(!state_of(plain).2.is_finite());
+        pl_world_destroy(h);
+        pl_world_destroy(plain);
+    }
+}
+
+extern "C" fn crushing_accel(_t: f64, _y: f64, _vy: f64, _user: *mut std::ffi::c_void) -> f64 {
+    -1e308
+}
+
+#[test]
+fn rollback_guards_every_stepping_call() {
+    let _guard = serial();
+    let mut never = f64::NEG_INFINITY;
+    let user = &mut never as *mut f64 as *mut std::ffi::c_void;
+    let (mut steps, mut err, mut kind) = (0, 0.0, 0);
+    let (mut t, mut y, mut vy) = (0.0, 0.0, 0.0);
+    unsafe {
+        pl_reset_for_testing();
+        // As above, every world's second unit step overflows.
+        let worlds: Vec<u64> = (0..8)
+            .map(|_| {
+                let h = pl_world_create(0.0, 0.0);
+                pl_world_set_gravity(h, 1e308);
+                pl_world_set_rollback(h, 1);
+                h
+            })
+            .collect();
+        let partner = pl_world_create(0.0, 0.0);
+        pl_link_spring(worlds[5], partner, 0.0, 0.0);
+        let codes = [
+            pl_world_step_unchecked(worlds[0], 1.0, 5),
+            pl_world_step_custom(worlds[1], 1.0, 5, Some(crushing_accel), std::ptr::null_mut()),
+            pl_world_step_with_error(worlds[2], 1.0, 5, &mut err),
+            pl_world_step_until(worlds[3], 1.0, 5, Some(below_threshold), user, &mut steps),
+            pl_world_step_to_rest(worlds[4], 1.0, 1e-6, 1e-6, &mut steps),
+            pl_step_linked(1.0, 5),
+        ];
+        assert_eq!(codes, [NUMERICAL_OVERFLOW; 6]);
+        for &h in &worlds[..6] {
+            assert_eq!(pl_world_last_error_code(h), NUMERICAL_OVERFLOW, "world {h}");
+            assert_eq!(state_of(h), (1.0, -1e308, -1e308), "world {h}");
+        }
+        assert_eq!(steps, 1);
+        // The linked partner keeps stepping without rollback.
+        assert_eq!(state_of(partner).0, 5.0);
+        assert_eq!(pl_world_last_error_code(partner), OK);
+
+        // A probe stops its copy at the last good state.
+        assert_eq!(pl_world_probe(worlds[6], 1.0, 5, &mut t, &mut y, &mut vy), NUMERICAL_OVERFLOW);
+        assert_eq!((t, y, vy), (1.0, -1e308, -1e308));
+        assert_eq!(state_of(worlds[6]), (0.0, 0.0, 0.0));
+
+        // An event search is undone as a whole.
+        assert_eq!(pl_world_step_to_event(worlds[7], 5.0, &mut t, &mut kind), NUMERICAL_OVERFLOW);
+        assert_eq!(state_of(worlds[7]), (0.0, 0.0, 0.0));
+        let mut diverged = 0;
+        pl_world_has_diverged(worlds[7], &mut diverged);
+        assert_eq!(diverged, 1);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn rollback_divergence_survives_snapshot() {
+    let _guard = serial();
+    unsafe {
+        pl_reset_for_testing();
+        let h = pl_world_create(0.0, 0.0);
+        pl_world_set_gravity(h, 1e308);
+        pl_world_set_rollback(h, 1);
+        assert_eq!(pl_world_step(h, 1.0, 5), NUMERICAL_OVERFLOW);
+        let needed = pl_snapshot_all(std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; needed as usize];
+        assert_eq!(pl_snapshot_all(buf.as_mut_ptr(), needed), needed);
+        pl_reset_for_testing();
+        assert_eq!(pl_restore_all(buf.as_ptr(), needed), OK);
+        assert_eq!(state_of(h), (1.0, -1e308, -1e308));
+        let mut diverged = 0;
+        assert_eq!(pl_world_has_diverged(h, &mut diverged), OK);
+        assert_eq!(diverged, 1);
+        pl_reset_for_testing();
+    }
+}
+
+#[test]
+fn error_message_len_matches_probe() {
+    let _guard = serial();
+    unsafe {
+        assert_eq!(pl_world_step(0, 0.01, 1), INVALID_HANDLE);
+        let len = pl_last_error_message_len();
+        assert_eq!(len, pl_last_error_message(std::ptr::null_mut(), 0));
+        let mut buf = vec![0xffu8; len as usize + 1];
+        pl_last_error_message(buf.as_mut_ptr(), len + 1);
+        assert_eq!(&buf[..len as usize], b"invalid handle");
+        assert_eq!(buf[len as usize], 0);
+
+        pl_world_count();
+        assert_eq!(pl_last_error_message_len(), 0);
+    }
+}
+
+#[test]
+fn gravity_units_convert_to_si() {
+    let _guard = serial();
+    let mut g = 0.0;
+    unsafe {
+        let h = pl_world_create(0.0, 0.0);
+        assert_eq!(pl_world_set_gravity_units(h, 1, 32.0), OK);
+        pl_world_get_gravity(h, &mut g);
+        assert_eq!(g, 32.0 * 0.3048);
+        assert_eq!(pl_world_set_gravity_units(h, 2, 1.0), OK);
+        pl_world_get_gravity(h, &mut g);
+        assert_eq!(g, 9.80665);
+        assert_eq!(pl_world_set_gravity_units(h, 0, 3.7), OK);
+        pl_world_get_gravity(h, &mut g);
+        assert_eq!(g, 3.7);
+
+        assert_eq!(pl_world_set_gravity_units(h, 3, 1.0), INVALID_ARGUMENT);
+        assert_eq!(pl_world_set_gravity_units(h, 2, f64::MAX), INVALID_ARGUMENT);
+        pl_world_get_gravity(h, &mut g);
+        assert_eq!(g, 3.7);
+        assert_eq!(pl_world_set_gravity_units(0, 0, 1.0), INVALID_HANDLE);
+        pl_world_destroy(h);
+    }
+}